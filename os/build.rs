@@ -0,0 +1,321 @@
+//! Converts the BDF fonts in `fonts/` into embedded-graphics `MonoFont`s and the PBM icons in
+//! `assets/icons/` into `ImageRaw`s, so the binary stays self-contained.
+
+#[path = "src/font/charset.rs"]
+mod charset;
+
+use std::{collections::BTreeMap, env, fmt::Write, fs, path::Path};
+
+/// Glyphs per row in the generated font image, same as the embedded-graphics fonts.
+const GLYPHS_PER_ROW: u32 = 16;
+
+const ICON_DIR: &str = "assets/icons";
+
+struct FontSpec {
+    name: &'static str,
+    file: &'static str,
+    doc: &'static str,
+}
+
+const FONTS: &[FontSpec] = &[
+    FontSpec {
+        name: "FONT_4X6",
+        file: "fonts/4x6.bdf",
+        doc: "Tiny font for status text.",
+    },
+    FontSpec {
+        name: "FONT_SEG7_14X24",
+        file: "fonts/seg7-14x24.bdf",
+        doc: "Big 7-segment style digits for the clock, covers `0-9`, `:`, `-` and space.",
+    },
+];
+
+struct Glyph {
+    bbx: (u32, u32, i32, i32),
+    advance: u32,
+    rows: Vec<u32>,
+}
+
+struct Bdf {
+    bounding_box: (u32, u32, i32, i32),
+    advance: u32,
+    glyphs: BTreeMap<char, Glyph>,
+}
+
+fn main() {
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR not set");
+    fonts(Path::new(&out_dir));
+    icons(Path::new(&out_dir));
+}
+
+fn fonts(out_dir: &Path) {
+    let mut code = String::new();
+
+    for spec in FONTS {
+        println!("cargo:rerun-if-changed={}", spec.file);
+        let source = fs::read_to_string(spec.file)
+            .unwrap_or_else(|err| panic!("fails to read {}: {err}", spec.file));
+        let bdf =
+            parse(&source).unwrap_or_else(|err| panic!("fails to parse {}: {err}", spec.file));
+        let raw = format!("{}.raw", spec.name.to_lowercase());
+        code += &generate(spec, &bdf, &out_dir.join(&raw), &raw);
+    }
+
+    fs::write(out_dir.join("fonts.rs"), code).expect("fails to write fonts.rs");
+    println!("cargo:rerun-if-changed=src/font/charset.rs");
+}
+
+fn icons(out_dir: &Path) {
+    println!("cargo:rerun-if-changed={ICON_DIR}");
+
+    let mut paths = fs::read_dir(ICON_DIR)
+        .expect("fails to read icon dir")
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "pbm"))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    let mut code = String::new();
+    for path in paths {
+        println!("cargo:rerun-if-changed={}", path.display());
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .expect("icon file name is not valid UTF-8")
+            .replace('-', "_");
+        let data =
+            fs::read(&path).unwrap_or_else(|err| panic!("fails to read {}: {err}", path.display()));
+        let (width, height, image) = parse_pbm(&data)
+            .unwrap_or_else(|err| panic!("fails to parse {}: {err}", path.display()));
+
+        let raw = format!("icon_{name}.raw");
+        fs::write(out_dir.join(&raw), image).expect("fails to write icon image");
+        writeln!(
+            code,
+            r#"/// {width}x{height} px, from `{path}`.
+pub const {name}: embedded_graphics::image::ImageRaw<embedded_graphics::pixelcolor::BinaryColor> =
+    embedded_graphics::image::ImageRaw::new(include_bytes!(concat!(env!("OUT_DIR"), "/{raw}")), {width});
+"#,
+            path = path.display(),
+            name = name.to_uppercase(),
+        )
+        .expect("fails to generate icon");
+    }
+
+    fs::write(out_dir.join("icons.rs"), code).expect("fails to write icons.rs");
+}
+
+/// Parses a plain (`P1`) or raw (`P4`) PBM image into rows of packed bits, MSB first.
+fn parse_pbm(data: &[u8]) -> Result<(u32, u32, Vec<u8>), String> {
+    // header: magic, width and height separated by whitespace, `#` starts a comment
+
+    let mut pos = 0;
+    let mut header = Vec::new();
+    while header.len() < 3 {
+        while pos < data.len() && (data[pos].is_ascii_whitespace() || data[pos] == b'#') {
+            if data[pos] == b'#' {
+                while pos < data.len() && data[pos] != b'\n' {
+                    pos += 1;
+                }
+            } else {
+                pos += 1;
+            }
+        }
+        let start = pos;
+        while pos < data.len() && !data[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        if start == pos {
+            return Err("truncated header".into());
+        }
+        header.push(String::from_utf8_lossy(&data[start..pos]).to_string());
+    }
+
+    let width = header[1]
+        .parse::<u32>()
+        .map_err(|err| format!("width: {err}"))?;
+    let height = header[2]
+        .parse::<u32>()
+        .map_err(|err| format!("height: {err}"))?;
+    let stride = width.div_ceil(8) as usize;
+    let len = stride * height as usize;
+
+    match header[0].as_str() {
+        "P4" => data
+            .get(pos + 1..pos + 1 + len)
+            .map(|image| (width, height, image.to_vec()))
+            .ok_or("truncated image data".into()),
+        "P1" => {
+            let bits = data[pos..]
+                .iter()
+                .filter(|b| **b == b'0' || **b == b'1')
+                .map(|b| *b == b'1')
+                .collect::<Vec<_>>();
+            if bits.len() < (width * height) as usize {
+                return Err("truncated image data".into());
+            }
+
+            let mut image = vec![0u8; len];
+            for y in 0..height as usize {
+                for x in 0..width as usize {
+                    if bits[y * width as usize + x] {
+                        image[y * stride + x / 8] |= 0x80 >> (x % 8);
+                    }
+                }
+            }
+            Ok((width, height, image))
+        }
+        magic => Err(format!("unsupported format {magic}, expected P1 or P4")),
+    }
+}
+
+fn parse(source: &str) -> Result<Bdf, String> {
+    let mut bounding_box = None;
+    let mut glyphs = BTreeMap::new();
+
+    let mut encoding = None;
+    let mut advance = None;
+    let mut bbx = None;
+    let mut rows: Option<Vec<u32>> = None;
+
+    for line in source.lines() {
+        let keyword = line.split_whitespace().next().unwrap_or_default();
+        let numbers = || {
+            line.split_whitespace()
+                .skip(1)
+                .map(|n| n.parse::<i32>().map_err(|err| format!("{line}: {err}")))
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        // bitmap rows until the end of the current glyph
+
+        if let Some(bitmap) = rows.as_mut() {
+            if keyword == "ENDCHAR" {
+                let bitmap = rows.take().unwrap_or_default();
+                let c = encoding.take().and_then(|e: i32| char::from_u32(e as u32));
+                if let (Some(c), Some(bbx), Some(advance)) = (c, bbx.take(), advance.take())
+                    && charset::supports(c)
+                {
+                    glyphs.insert(
+                        c,
+                        Glyph {
+                            bbx,
+                            advance,
+                            rows: bitmap,
+                        },
+                    );
+                }
+            } else {
+                let value =
+                    u32::from_str_radix(keyword, 16).map_err(|err| format!("{line}: {err}"))?;
+                // left align the row in 32 bits, BDF pads rows to whole bytes
+                bitmap.push(value << (32 - 4 * keyword.len() as u32));
+            }
+            continue;
+        }
+
+        match keyword {
+            "FONTBOUNDINGBOX" | "BBX" => {
+                let n = numbers()?;
+                if n.len() != 4 {
+                    return Err(format!("{line}: expected 4 numbers"));
+                }
+                let value = (n[0] as u32, n[1] as u32, n[2], n[3]);
+                if keyword == "BBX" {
+                    bbx = Some(value);
+                } else {
+                    bounding_box = Some(value);
+                }
+            }
+            "DWIDTH" => advance = numbers()?.first().map(|&width| width as u32),
+            "ENCODING" => encoding = numbers()?.first().copied(),
+            "BITMAP" => rows = Some(Vec::new()),
+            _ => {}
+        }
+    }
+
+    let bounding_box = bounding_box.ok_or("missing FONTBOUNDINGBOX")?;
+    let advance = glyphs.values().map(|g| g.advance).max().unwrap_or_default();
+    if glyphs.values().any(|g| g.advance != advance) {
+        return Err("only monospaced fonts are supported".into());
+    }
+    if !glyphs.contains_key(&'?') {
+        return Err("font needs a `?` glyph as replacement".into());
+    }
+
+    Ok(Bdf {
+        bounding_box,
+        advance,
+        glyphs,
+    })
+}
+
+fn generate(spec: &FontSpec, bdf: &Bdf, raw_path: &Path, raw_name: &str) -> String {
+    let (width, height, x_offset, y_offset) = bdf.bounding_box;
+    let baseline = height as i32 + y_offset - 1;
+
+    // draw all glyphs into one 1 bit image, row by row
+
+    let count = bdf.glyphs.len() as u32;
+    let image_width = GLYPHS_PER_ROW * width;
+    let image_height = count.div_ceil(GLYPHS_PER_ROW) * height;
+    let stride = image_width.div_ceil(8);
+    let mut image = vec![0u8; (stride * image_height) as usize];
+
+    for (index, glyph) in bdf.glyphs.values().enumerate() {
+        let cell_x = (index as u32 % GLYPHS_PER_ROW) * width;
+        let cell_y = (index as u32 / GLYPHS_PER_ROW) * height;
+        let (glyph_width, glyph_height, glyph_x, glyph_y) = glyph.bbx;
+
+        // position of the glyph's bounding box within the font's bounding box
+        let left = glyph_x - x_offset;
+        let top = (height as i32 + y_offset) - (glyph_y + glyph_height as i32);
+
+        for (row, bits) in glyph.rows.iter().enumerate() {
+            for column in 0..glyph_width.min(32) {
+                if bits & (1 << (31 - column)) == 0 {
+                    continue;
+                }
+                let x = left + column as i32;
+                let y = top + row as i32;
+                if x < 0 || y < 0 || x >= width as i32 || y >= height as i32 {
+                    continue;
+                }
+                let x = cell_x + x as u32;
+                let y = cell_y + y as u32;
+                image[(y * stride + x / 8) as usize] |= 0x80 >> (x % 8);
+            }
+        }
+    }
+
+    fs::write(raw_path, image).expect("fails to write font image");
+
+    let chars = bdf.glyphs.keys().collect::<String>();
+    let replacement = bdf
+        .glyphs
+        .keys()
+        .position(|&c| c == '?')
+        .unwrap_or_default();
+    let spacing = bdf.advance.saturating_sub(width);
+
+    let mut code = String::new();
+    writeln!(
+        code,
+        r#"/// {doc}
+pub const {name}: embedded_graphics::mono_font::MonoFont = embedded_graphics::mono_font::MonoFont {{
+    image: embedded_graphics::image::ImageRaw::new(include_bytes!(concat!(env!("OUT_DIR"), "/{raw_name}")), {image_width}),
+    glyph_mapping: &embedded_graphics::mono_font::mapping::StrGlyphMapping::new({chars:?}, {replacement}),
+    character_size: embedded_graphics::geometry::Size::new({width}, {height}),
+    character_spacing: {spacing},
+    baseline: {baseline},
+    underline: embedded_graphics::mono_font::DecorationDimensions::new({baseline} + 2, 1),
+    strikethrough: embedded_graphics::mono_font::DecorationDimensions::new({height} / 2, 1),
+}};
+"#,
+        doc = spec.doc,
+        name = spec.name,
+    )
+    .expect("fails to generate font");
+
+    code
+}
@@ -0,0 +1,120 @@
+use crate::message;
+use anyhow::{Context, Result};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Timelike, Weekday};
+use log::trace;
+use serde::{Deserialize, Serialize};
+use std::env;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Alarm {
+    pub hour: u32,
+    pub minute: u32,
+    /// Weekdays the alarm repeats on, it rings only once if empty.
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+    /// Day of a one-off alarm, rings at the next matching time if not set.
+    #[serde(default)]
+    pub date: Option<NaiveDate>,
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+/// Repeat presets offered by the on-device editor.
+pub const PRESETS: [&[Weekday]; 4] = [
+    &[],
+    &[
+        Weekday::Mon,
+        Weekday::Tue,
+        Weekday::Wed,
+        Weekday::Thu,
+        Weekday::Fri,
+        Weekday::Sat,
+        Weekday::Sun,
+    ],
+    &[
+        Weekday::Mon,
+        Weekday::Tue,
+        Weekday::Wed,
+        Weekday::Thu,
+        Weekday::Fri,
+    ],
+    &[Weekday::Sat, Weekday::Sun],
+];
+
+impl Default for Alarm {
+    fn default() -> Self {
+        Alarm {
+            hour: 7,
+            minute: 0,
+            days: Vec::new(),
+            date: None,
+            enabled: true,
+        }
+    }
+}
+
+impl Alarm {
+    /// Whether the alarm rings at the local minute `at`.
+    pub fn rings_at(&self, at: NaiveDateTime) -> bool {
+        if !self.enabled || at.hour() != self.hour || at.minute() != self.minute {
+            return false;
+        }
+
+        if self.days.is_empty() {
+            self.date.is_none_or(|date| date == at.date())
+        } else {
+            self.days.contains(&at.weekday())
+        }
+    }
+
+    pub fn is_repeating(&self) -> bool {
+        !self.days.is_empty()
+    }
+
+    /// Short label of the days the alarm rings on, e.g. `Mo-Fr` or `Mo We`.
+    pub fn repeat_label(&self) -> String {
+        if let Some(date) = self.date.filter(|_| self.days.is_empty()) {
+            return date.format("%d.%m.").to_string();
+        }
+
+        match PRESETS.iter().position(|preset| self.has_days(preset)) {
+            Some(0) => "once".into(),
+            Some(1) => "daily".into(),
+            Some(2) => "Mo-Fr".into(),
+            Some(3) => "Sa Su".into(),
+            _ => self
+                .days
+                .iter()
+                .map(|day| day.to_string()[..2].to_string())
+                .collect::<Vec<_>>()
+                .join(" "),
+        }
+    }
+
+    pub fn has_days(&self, days: &[Weekday]) -> bool {
+        self.days.len() == days.len() && days.iter().all(|day| self.days.contains(day))
+    }
+}
+
+/// Fetches the alarms set on the message server.
+pub fn fetch() -> Result<Vec<Alarm>> {
+    let url = env::var("MESSAGE_URL").context("MESSAGE_URL env var not set")?;
+    let url = format!("{}/alarms", url.trim_end_matches('/'));
+
+    trace!("fetching alarms from {}", url);
+
+    let client = reqwest::blocking::Client::new();
+    let response = message::authorize(client.get(&url))?
+        .send()
+        .context("failed to fetch alarms")?;
+
+    if !response.status().is_success() {
+        anyhow::bail!("failed to fetch alarms: status {}", response.status());
+    }
+
+    response.json().context("failed to parse alarms")
+}
@@ -0,0 +1,487 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use log::{trace, warn};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, env, fs};
+
+mod recurrence;
+
+use recurrence::Rule;
+
+/// A single occurrence of a calendar event.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Event {
+    pub summary: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub all_day: bool,
+}
+
+/// Whether any calendar is configured in `CALENDAR_URLS`.
+pub fn is_configured() -> bool {
+    env::var("CALENDAR_URLS").is_ok_and(|urls| !urls.trim().is_empty())
+}
+
+/// Fetches the events between `from` and `to` from all ICS URLs or local `.ics` files in
+/// `CALENDAR_URLS`, sorted by start. Floating times are read in `timezone`.
+pub fn fetch(from: DateTime<Utc>, to: DateTime<Utc>, timezone: Tz) -> Result<Vec<Event>> {
+    let sources = env::var("CALENDAR_URLS").context("CALENDAR_URLS env var not set")?;
+
+    let mut events = Vec::new();
+    for source in sources.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        trace!("fetching calendar from {}", source);
+
+        let ics = if source.starts_with("http://") || source.starts_with("https://") {
+            reqwest::blocking::get(source)
+                .and_then(|response| response.error_for_status())
+                .and_then(|response| response.text())
+                .with_context(|| format!("failed to fetch calendar {source}"))?
+        } else {
+            fs::read_to_string(source).with_context(|| format!("failed to read {source}"))?
+        };
+        events.extend(parse(&ics, from, to, timezone));
+    }
+
+    // the same event may come from several calendars
+
+    let mut seen = HashSet::new();
+    events.retain(|event| seen.insert((event.summary.clone(), event.start)));
+    events.sort_by_key(|event| (event.start, !event.all_day));
+    Ok(events)
+}
+
+/// Parses the VEVENTs of an ICS calendar and expands them into the occurrences overlapping
+/// `from` to `to`.
+pub fn parse(ics: &str, from: DateTime<Utc>, to: DateTime<Utc>, timezone: Tz) -> Vec<Event> {
+    let components = components(ics, timezone);
+
+    // moved or cancelled occurrences of recurring events replace the regular ones
+
+    let overridden = components
+        .iter()
+        .filter_map(|c| Some((c.uid.clone()?, c.recurrence_id?)))
+        .collect::<HashSet<_>>();
+
+    let mut events = Vec::new();
+    for component in components {
+        let Some(start) = component.start else {
+            continue;
+        };
+        let zone = component.zone.unwrap_or(timezone);
+        let duration = component.end.map_or(
+            if component.all_day {
+                Duration::days(1)
+            } else {
+                Duration::zero()
+            },
+            |end| end - start,
+        );
+        let rule = component
+            .rrule
+            .as_deref()
+            .filter(|_| component.recurrence_id.is_none())
+            .and_then(|rrule| Rule::parse(rrule, zone));
+
+        // limit the expansion to the local time range of the request
+
+        let until = to.with_timezone(&zone).naive_local();
+        let starts = match rule {
+            Some(rule) => rule.occurrences(start, until),
+            None => vec![start],
+        };
+
+        for local in starts {
+            if component.exdates.contains(&local) {
+                continue;
+            }
+            if let Some(uid) = &component.uid
+                && component.recurrence_id.is_none()
+                && overridden.contains(&(uid.clone(), local))
+            {
+                continue;
+            }
+            if component.cancelled {
+                continue;
+            }
+
+            let Some(start) = resolve(local, zone) else {
+                continue;
+            };
+            let end = resolve(local + duration, zone).unwrap_or(start);
+            if start < to && (end > from || start >= from) {
+                events.push(Event {
+                    summary: component.summary.clone(),
+                    start,
+                    end,
+                    all_day: component.all_day,
+                });
+            }
+        }
+    }
+
+    events
+}
+
+/// The properties of a VEVENT needed for the agenda, times in the event's local time.
+#[derive(Default)]
+struct Component {
+    uid: Option<String>,
+    summary: String,
+    start: Option<NaiveDateTime>,
+    end: Option<NaiveDateTime>,
+    zone: Option<Tz>,
+    all_day: bool,
+    rrule: Option<String>,
+    exdates: Vec<NaiveDateTime>,
+    recurrence_id: Option<NaiveDateTime>,
+    cancelled: bool,
+}
+
+/// A time as written in the calendar, in UTC, in a `TZID` zone or floating.
+struct Stamp {
+    time: NaiveDateTime,
+    utc: bool,
+    zone: Option<Tz>,
+}
+
+impl Stamp {
+    fn parse(value: &str, zone: Option<Tz>) -> Option<Stamp> {
+        parse_time(value).map(|(time, utc)| Stamp { time, utc, zone })
+    }
+
+    fn localize(&self, target: Tz) -> NaiveDateTime {
+        localize(self.time, self.utc, self.zone, Some(target))
+    }
+}
+
+/// Times of a VEVENT that depend on the zone of its `DTSTART`, which may come after them.
+#[derive(Default)]
+struct Pending {
+    end: Option<Stamp>,
+    duration: Option<Duration>,
+    exdates: Vec<Stamp>,
+    recurrence_id: Option<Stamp>,
+}
+
+fn components(ics: &str, timezone: Tz) -> Vec<Component> {
+    let mut components = Vec::new();
+    let mut current: Option<Component> = None;
+    let mut pending = Pending::default();
+
+    for line in unfold(ics) {
+        let Some((name, params, value)) = property(&line) else {
+            continue;
+        };
+
+        match (name.as_str(), value) {
+            ("BEGIN", "VEVENT") => {
+                current = Some(Component::default());
+                pending = Pending::default();
+            }
+            ("END", "VEVENT") => {
+                if let Some(mut component) = current.take() {
+                    // floating times of the event are read in the calendar's zone
+                    let target = component.zone.unwrap_or(timezone);
+                    let pending = std::mem::take(&mut pending);
+
+                    component.end = match pending.end {
+                        Some(end) => Some(end.localize(target)),
+                        None => component.start.zip(pending.duration).map(|(s, d)| s + d),
+                    };
+                    component.exdates = pending
+                        .exdates
+                        .iter()
+                        .map(|exdate| exdate.localize(target))
+                        .collect();
+                    component.recurrence_id = pending.recurrence_id.map(|id| id.localize(target));
+                    components.push(component);
+                }
+            }
+            _ => {
+                let Some(component) = current.as_mut() else {
+                    continue;
+                };
+                let zone = param(&params, "TZID").and_then(|tzid| parse_zone(&tzid));
+
+                match name.as_str() {
+                    "UID" => component.uid = Some(value.to_string()),
+                    "SUMMARY" => component.summary = unescape(value),
+                    "STATUS" => component.cancelled = value.eq_ignore_ascii_case("CANCELLED"),
+                    "RRULE" => component.rrule = Some(value.to_string()),
+                    "DURATION" => pending.duration = parse_duration(value),
+                    "DTSTART" => {
+                        if let Some((start, utc)) = parse_time(value) {
+                            component.start = Some(start);
+                            component.all_day = value.len() == 8;
+                            component.zone = if utc { Some(chrono_tz::UTC) } else { zone };
+                        }
+                    }
+                    "DTEND" => pending.end = Stamp::parse(value, zone),
+                    "EXDATE" => pending.exdates.extend(
+                        value
+                            .split(',')
+                            .filter_map(|exdate| Stamp::parse(exdate, zone)),
+                    ),
+                    "RECURRENCE-ID" => pending.recurrence_id = Stamp::parse(value, zone),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    components
+}
+
+/// Joins folded lines, continuation lines start with a space or tab.
+fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// Property parameters like `TZID=Europe/Berlin` as key and value.
+type Params = Vec<(String, String)>;
+
+/// Splits `NAME;PARAM=VALUE:value` into its name, parameters and value.
+fn property(line: &str) -> Option<(String, Params, &str)> {
+    // the value starts at the first colon outside of quoted parameter values
+
+    let mut quoted = false;
+    let colon = line.char_indices().find_map(|(i, c)| {
+        match c {
+            '"' => quoted = !quoted,
+            ':' if !quoted => return Some(i),
+            _ => {}
+        }
+        None
+    })?;
+
+    let mut parts = line[..colon].split(';');
+    let name = parts.next()?.to_uppercase();
+    let params = parts
+        .filter_map(|param| param.split_once('='))
+        .map(|(key, value)| (key.to_uppercase(), value.trim_matches('"').to_string()))
+        .collect();
+
+    Some((name, params, &line[colon + 1..]))
+}
+
+fn param(params: &[(String, String)], name: &str) -> Option<String> {
+    params
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.clone())
+}
+
+fn parse_zone(tzid: &str) -> Option<Tz> {
+    // some calendars prefix the IANA name with a path, e.g. `/citadel.org/.../Europe/Berlin`
+
+    let zone = tzid.parse::<Tz>().ok().or_else(|| {
+        let mut parts = tzid.rsplit('/');
+        let city = parts.next()?;
+        let region = parts.next()?;
+        format!("{region}/{city}").parse::<Tz>().ok()
+    });
+    if zone.is_none() {
+        warn!("unknown calendar time zone {tzid}");
+    }
+    zone
+}
+
+/// Parses `20261018`, `20261018T090000` or `20261018T090000Z`, returns whether it is UTC.
+fn parse_time(value: &str) -> Option<(NaiveDateTime, bool)> {
+    let value = value.trim();
+    if let Some(utc) = value.strip_suffix('Z') {
+        return NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")
+            .ok()
+            .map(|t| (t, true));
+    }
+
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y%m%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+        .map(|t| (t, false))
+}
+
+/// Parses durations like `PT1H30M` or `P1D`.
+fn parse_duration(value: &str) -> Option<Duration> {
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(value) => (true, value),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let value = value.strip_prefix('P')?;
+
+    let mut duration = Duration::zero();
+    let mut number = String::new();
+    let mut time = false;
+    for c in value.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => time = true,
+            unit => {
+                let n = number.parse::<i64>().ok()?;
+                number.clear();
+                duration += match (unit, time) {
+                    ('W', false) => Duration::weeks(n),
+                    ('D', false) => Duration::days(n),
+                    ('H', true) => Duration::hours(n),
+                    ('M', true) => Duration::minutes(n),
+                    ('S', true) => Duration::seconds(n),
+                    _ => return None,
+                };
+            }
+        }
+    }
+
+    Some(if negative { -duration } else { duration })
+}
+
+fn unescape(value: &str) -> String {
+    let mut text = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n' | 'N') => text.push(' '),
+            Some(escaped) => text.push(escaped),
+            None => {}
+        }
+    }
+    text
+}
+
+/// Moves a time given in UTC or `zone` into the local time of `target`, the event's zone.
+fn localize(time: NaiveDateTime, utc: bool, zone: Option<Tz>, target: Option<Tz>) -> NaiveDateTime {
+    let zone = if utc { Some(chrono_tz::UTC) } else { zone };
+    match (zone, target) {
+        (Some(zone), Some(target)) if zone != target => {
+            resolve(time, zone).map_or(time, |time| time.with_timezone(&target).naive_local())
+        }
+        _ => time,
+    }
+}
+
+/// Local time in `zone` as UTC, times skipped by a DST change are moved past the gap.
+fn resolve(local: NaiveDateTime, zone: Tz) -> Option<DateTime<Utc>> {
+    zone.from_local_datetime(&local)
+        .earliest()
+        .or_else(|| {
+            zone.from_local_datetime(&(local + Duration::hours(1)))
+                .earliest()
+        })
+        .map(|time| time.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::Europe::Berlin;
+
+    fn utc(time: &str) -> DateTime<Utc> {
+        NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M")
+            .unwrap()
+            .and_utc()
+    }
+
+    fn event(summary: &str, start: &str, end: &str, all_day: bool) -> Event {
+        Event {
+            summary: summary.to_string(),
+            start: utc(start),
+            end: utc(end),
+            all_day,
+        }
+    }
+
+    fn parse_week(ics: &str, timezone: Tz) -> Vec<Event> {
+        let mut events = parse(
+            ics,
+            utc("2026-03-02 00:00"),
+            utc("2026-03-11 00:00"),
+            timezone,
+        );
+        events.sort_by_key(|event| event.start);
+        events
+    }
+
+    #[test]
+    fn expands_recurrences_with_exceptions_and_overrides() {
+        let events = parse_week(include_str!("../../fixtures/calendar/family.ics"), Berlin);
+
+        assert_eq!(
+            events,
+            vec![
+                event(
+                    "Team standup, daily",
+                    "2026-03-02 08:15",
+                    "2026-03-02 08:30",
+                    false
+                ),
+                event("Dentist", "2026-03-03 17:00", "2026-03-03 18:00", false),
+                event(
+                    "Grandma's birthday",
+                    "2026-03-04 23:00",
+                    "2026-03-05 23:00",
+                    true
+                ),
+                event(
+                    "Standup moved",
+                    "2026-03-06 10:00",
+                    "2026-03-06 10:30",
+                    false
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn localizes_times_written_before_the_start() {
+        let events = parse_week(
+            include_str!("../../fixtures/calendar/outlook.ics"),
+            chrono_tz::UTC,
+        );
+
+        assert_eq!(
+            events,
+            vec![
+                event("Review", "2026-03-02 13:00", "2026-03-02 14:00", false),
+                event(
+                    "1:1 after lunch",
+                    "2026-03-10 11:00",
+                    "2026-03-10 11:30",
+                    false
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("PT1H30M"), Some(Duration::minutes(90)));
+        assert_eq!(parse_duration("P1W2D"), Some(Duration::days(9)));
+        assert_eq!(parse_duration("-PT15M"), Some(Duration::minutes(-15)));
+        assert_eq!(parse_duration("P1H"), None);
+    }
+
+    #[test]
+    fn parses_prefixed_zones() {
+        assert_eq!(
+            parse_zone("/citadel.org/20250101_1/Europe/Berlin"),
+            Some(Berlin)
+        );
+        assert_eq!(parse_zone("W. Europe Standard Time"), None);
+    }
+}
@@ -0,0 +1,324 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Weekday};
+use chrono_tz::Tz;
+use log::warn;
+
+/// Upper bound of periods expanded for a single rule, e.g. days of a daily rule.
+const MAX_PERIODS: u32 = 100_000;
+
+#[derive(Clone, Copy)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// The supported subset of an RRULE: `FREQ`, `INTERVAL`, `COUNT`, `UNTIL`, `BYDAY` and
+/// `BYMONTHDAY`.
+pub struct Rule {
+    frequency: Frequency,
+    interval: u32,
+    count: Option<usize>,
+    until: Option<NaiveDateTime>,
+    /// Weekdays, with an optional ordinal within the month like `-1` for `-1FR`.
+    by_day: Vec<(Option<i32>, Weekday)>,
+    by_month_day: Vec<i32>,
+}
+
+impl Rule {
+    /// Parses e.g. `FREQ=WEEKLY;BYDAY=MO,WE;UNTIL=20261231T000000Z`, with `UNTIL` moved into
+    /// `zone`, the local time of the event.
+    pub fn parse(rrule: &str, zone: Tz) -> Option<Rule> {
+        let mut rule = Rule {
+            frequency: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+        };
+        let mut frequency = None;
+
+        for part in rrule.split(';') {
+            let Some((key, value)) = part.split_once('=') else {
+                continue;
+            };
+
+            match key.to_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = match value.to_uppercase().as_str() {
+                        "DAILY" => Some(Frequency::Daily),
+                        "WEEKLY" => Some(Frequency::Weekly),
+                        "MONTHLY" => Some(Frequency::Monthly),
+                        "YEARLY" => Some(Frequency::Yearly),
+                        _ => None,
+                    }
+                }
+                "INTERVAL" => rule.interval = value.parse().unwrap_or(1).max(1),
+                "COUNT" => rule.count = value.parse().ok(),
+                "UNTIL" => {
+                    rule.until = super::parse_time(value).map(|(until, utc)| {
+                        // a date only until includes the whole day
+                        if value.len() == 8 {
+                            until + Duration::days(1) - Duration::seconds(1)
+                        } else {
+                            super::localize(until, utc, None, Some(zone))
+                        }
+                    })
+                }
+                "BYDAY" => rule.by_day = value.split(',').filter_map(parse_day).collect(),
+                "BYMONTHDAY" => {
+                    rule.by_month_day = value.split(',').filter_map(|d| d.parse().ok()).collect()
+                }
+                "WKST" => {}
+                _ => warn!("ignore unsupported recurrence part {part}"),
+            }
+        }
+
+        match frequency {
+            Some(frequency) => {
+                rule.frequency = frequency;
+                Some(rule)
+            }
+            None => {
+                warn!("skip unsupported recurrence {rrule}");
+                None
+            }
+        }
+    }
+
+    /// Start times of all occurrences from `start`, the first one, up to `until`.
+    pub fn occurrences(&self, start: NaiveDateTime, until: NaiveDateTime) -> Vec<NaiveDateTime> {
+        let until = self.until.map_or(until, |rule_until| rule_until.min(until));
+        let time = start.time();
+
+        let mut occurrences = Vec::new();
+        let mut counted = 0;
+        for period in 0..MAX_PERIODS {
+            for date in self.dates(start.date(), period * self.interval) {
+                let occurrence = date.and_time(time);
+                if occurrence < start {
+                    continue;
+                }
+                if occurrence > until || self.count.is_some_and(|count| counted >= count) {
+                    return occurrences;
+                }
+
+                counted += 1;
+                occurrences.push(occurrence);
+            }
+        }
+
+        occurrences
+    }
+
+    /// Dates of the rule in the period `offset` days, weeks, months or years after `start`,
+    /// in order.
+    fn dates(&self, start: NaiveDate, offset: u32) -> Vec<NaiveDate> {
+        let mut dates = match self.frequency {
+            Frequency::Daily => {
+                let date = start + Duration::days(offset.into());
+                let matches = self.by_day.is_empty()
+                    || self.by_day.iter().any(|(_, day)| *day == date.weekday());
+                if matches { vec![date] } else { Vec::new() }
+            }
+            Frequency::Weekly => {
+                let monday = start - Duration::days(start.weekday().num_days_from_monday().into())
+                    + Duration::weeks(offset.into());
+                if self.by_day.is_empty() {
+                    vec![monday + Duration::days(start.weekday().num_days_from_monday().into())]
+                } else {
+                    self.by_day
+                        .iter()
+                        .map(|(_, day)| monday + Duration::days(day.num_days_from_monday().into()))
+                        .collect()
+                }
+            }
+            Frequency::Monthly => {
+                let months = start.year() * 12 + start.month0() as i32 + offset as i32;
+                let (year, month) = (months / 12, months as u32 % 12 + 1);
+                self.month_dates(year, month, start.day())
+            }
+            Frequency::Yearly => {
+                NaiveDate::from_ymd_opt(start.year() + offset as i32, start.month(), start.day())
+                    .into_iter()
+                    .collect()
+            }
+        };
+
+        dates.sort();
+        dates.dedup();
+        dates
+    }
+
+    fn month_dates(&self, year: i32, month: u32, day: u32) -> Vec<NaiveDate> {
+        let Some(first) = NaiveDate::from_ymd_opt(year, month, 1) else {
+            return Vec::new();
+        };
+        let days = first
+            .checked_add_months(chrono::Months::new(1))
+            .map_or(31, |next| (next - first).num_days() as i32);
+
+        // negative month days count from the end, e.g. -1 is the last day
+        let day_of_month = |day: i32| {
+            let day = if day < 0 { days + day + 1 } else { day };
+            (1..=days)
+                .contains(&day)
+                .then(|| first.with_day(day as u32))
+                .flatten()
+        };
+
+        if !self.by_month_day.is_empty() {
+            return self
+                .by_month_day
+                .iter()
+                .filter_map(|day| day_of_month(*day))
+                .collect();
+        }
+        if self.by_day.is_empty() {
+            return day_of_month(day as i32).into_iter().collect();
+        }
+
+        let mut dates = Vec::new();
+        for (ordinal, weekday) in &self.by_day {
+            let weekdays = (1..=days)
+                .filter_map(day_of_month)
+                .filter(|date| date.weekday() == *weekday)
+                .collect::<Vec<_>>();
+            match ordinal {
+                Some(n) if *n > 0 => dates.extend(weekdays.get(*n as usize - 1)),
+                Some(n) if *n < 0 => dates.extend(
+                    weekdays
+                        .len()
+                        .checked_sub(n.unsigned_abs() as usize)
+                        .map(|i| weekdays[i]),
+                ),
+                _ => dates.extend(weekdays),
+            }
+        }
+        dates
+    }
+}
+
+/// Parses `MO`, `2TU` or `-1FR`.
+fn parse_day(value: &str) -> Option<(Option<i32>, Weekday)> {
+    let value = value.trim();
+    let split = value.len().checked_sub(2)?;
+    let weekday = match value.get(split..)? {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    };
+    let ordinal = match value.get(..split)? {
+        "" => None,
+        ordinal => Some(ordinal.trim_start_matches('+').parse().ok()?),
+    };
+    Some((ordinal, weekday))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::Europe::Berlin;
+
+    fn time(time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn occurrences(rrule: &str, start: &str, until: &str) -> Vec<String> {
+        Rule::parse(rrule, Berlin)
+            .unwrap()
+            .occurrences(time(start), time(until))
+            .iter()
+            .map(|occurrence| occurrence.format("%Y-%m-%d %H:%M").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn weekly_on_several_days() {
+        assert_eq!(
+            occurrences(
+                "FREQ=WEEKLY;BYDAY=MO,WE,FR",
+                "2026-03-04 09:00",
+                "2026-03-10 00:00"
+            ),
+            ["2026-03-04 09:00", "2026-03-06 09:00", "2026-03-09 09:00"]
+        );
+    }
+
+    #[test]
+    fn every_other_day_up_to_a_count() {
+        assert_eq!(
+            occurrences(
+                "FREQ=DAILY;INTERVAL=2;COUNT=3",
+                "2026-03-01 08:00",
+                "2027-01-01 00:00"
+            ),
+            ["2026-03-01 08:00", "2026-03-03 08:00", "2026-03-05 08:00"]
+        );
+    }
+
+    #[test]
+    fn last_friday_and_last_day_of_the_month() {
+        assert_eq!(
+            occurrences(
+                "FREQ=MONTHLY;BYDAY=-1FR",
+                "2026-01-30 17:00",
+                "2026-04-01 00:00"
+            ),
+            ["2026-01-30 17:00", "2026-02-27 17:00", "2026-03-27 17:00"]
+        );
+        assert_eq!(
+            occurrences(
+                "FREQ=MONTHLY;BYMONTHDAY=-1",
+                "2026-01-31 12:00",
+                "2026-04-01 00:00"
+            ),
+            ["2026-01-31 12:00", "2026-02-28 12:00", "2026-03-31 12:00"]
+        );
+    }
+
+    #[test]
+    fn yearly_skips_missing_days() {
+        assert_eq!(
+            occurrences("FREQ=YEARLY", "2024-02-29 00:00", "2029-01-01 00:00"),
+            ["2024-02-29 00:00", "2028-02-29 00:00"]
+        );
+    }
+
+    #[test]
+    fn date_only_until_includes_the_whole_day() {
+        assert_eq!(
+            occurrences(
+                "FREQ=DAILY;UNTIL=20260303",
+                "2026-03-01 18:00",
+                "2027-01-01 00:00"
+            ),
+            ["2026-03-01 18:00", "2026-03-02 18:00", "2026-03-03 18:00"]
+        );
+    }
+
+    #[test]
+    fn utc_until_is_read_in_the_event_zone() {
+        // 08:00 UTC is 09:00 in Berlin, so the occurrence at 09:00 is still included
+        assert_eq!(
+            occurrences(
+                "FREQ=DAILY;UNTIL=20260303T080000Z",
+                "2026-03-02 09:00",
+                "2027-01-01 00:00"
+            ),
+            ["2026-03-02 09:00", "2026-03-03 09:00"]
+        );
+    }
+
+    #[test]
+    fn requires_a_supported_frequency() {
+        assert!(Rule::parse("FREQ=HOURLY", Berlin).is_none());
+        assert!(Rule::parse("BYDAY=MO", Berlin).is_none());
+    }
+}
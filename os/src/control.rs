@@ -0,0 +1,305 @@
+use crate::{
+    frame::Frame,
+    mirror::{self, Screen},
+    state::Event,
+    weather::Condition,
+};
+use chrono::{DateTime, Utc};
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    env,
+    io::Read,
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, Receiver, Sender},
+    },
+    thread,
+};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// Largest body accepted for `POST /message`, messages are short anyway.
+const MAX_MESSAGE_BODY: usize = 4096;
+
+/// Status served at `GET /status`.
+#[derive(Clone, Serialize)]
+pub struct Status {
+    pub view: &'static str,
+    pub page: usize,
+    pub sleep: bool,
+    pub brightness: u8,
+    pub unread: bool,
+    pub fetched: Fetched,
+    pub weather: Option<Weather>,
+}
+
+/// When each source was last fetched successfully.
+#[derive(Clone, Default, Serialize)]
+pub struct Fetched {
+    pub weather: Option<DateTime<Utc>>,
+    pub departures: Option<DateTime<Utc>>,
+    pub message: Option<DateTime<Utc>>,
+    pub news: Option<DateTime<Utc>>,
+    pub calendar: Option<DateTime<Utc>>,
+    pub alarms: Option<DateTime<Utc>>,
+}
+
+/// The cached weather as shown on the weather view.
+#[derive(Clone, Serialize)]
+pub struct Weather {
+    pub temperature: f32,
+    pub relative_humidity_percent: f32,
+    pub surface_pressure_hpa: f32,
+    pub wind_speed_km_h: f32,
+    pub wind_direction_deg: i32,
+    pub rain_in_x_hours: Option<usize>,
+    pub condition: Condition,
+    pub failed: bool,
+}
+
+enum Command {
+    Next,
+    Previous,
+    ToggleSleep,
+    Message(String),
+}
+
+#[derive(Deserialize)]
+struct MessageBody {
+    text: String,
+}
+
+/// Local HTTP server to read the status, watch the screen and control the cube like with
+/// the knob.
+pub struct Control {
+    status: Arc<Mutex<Option<Status>>>,
+    screen: Arc<Screen>,
+    commands: Receiver<Command>,
+}
+
+/// What the request handlers share with the cube.
+struct Context {
+    token: String,
+    status: Arc<Mutex<Option<Status>>>,
+    screen: Arc<Screen>,
+    commands: Sender<Command>,
+}
+
+/// Starts the control server on `CONTROL_PORT`, if set. Every request needs the
+/// `CONTROL_TOKEN` as bearer token.
+pub fn start() -> Option<Control> {
+    let port = env::var("CONTROL_PORT")
+        .ok()
+        .filter(|port| !port.is_empty())?;
+    let Some(token) = env::var("CONTROL_TOKEN")
+        .ok()
+        .filter(|token| !token.is_empty())
+    else {
+        error!("CONTROL_TOKEN env var not set, control server not started");
+        return None;
+    };
+    let server = match Server::http(format!("0.0.0.0:{port}")) {
+        Ok(server) => server,
+        Err(err) => {
+            error!("failed to start control server on port {port}: {err:?}");
+            return None;
+        }
+    };
+    info!("serve control api on port {port}");
+
+    let status = Arc::new(Mutex::new(None));
+    let screen = Arc::new(Screen::new());
+    let (sender, commands) = mpsc::channel();
+
+    let context = Context {
+        token,
+        status: status.clone(),
+        screen: screen.clone(),
+        commands: sender,
+    };
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            handle(request, &context);
+        }
+    });
+
+    Some(Control {
+        status,
+        screen,
+        commands,
+    })
+}
+
+fn handle(mut request: Request, context: &Context) {
+    debug!("control request {} {}", request.method(), request.url());
+
+    let authorized = request.headers().iter().any(|header| {
+        header.field.equiv("Authorization")
+            && header
+                .value
+                .as_str()
+                .strip_prefix("Bearer ")
+                .is_some_and(|token| same_token(token, &context.token))
+    });
+    if !authorized {
+        respond(request, 401, json!({ "error": "unauthorized" }));
+        return;
+    }
+
+    let path = request
+        .url()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let command = match (request.method(), path.as_str()) {
+        (Method::Get, "/status") => {
+            let status = context.status.lock().ok().and_then(|status| status.clone());
+            respond(request, 200, json!(status));
+            return;
+        }
+        (Method::Get, "/screen.png") => {
+            match mirror::png(&context.screen.get()) {
+                Ok(png) => {
+                    let header =
+                        Header::from_bytes("Content-Type", "image/png").expect("valid header");
+                    if let Err(err) = request.respond(Response::from_data(png).with_header(header))
+                    {
+                        warn!("failed to respond to control request: {err:?}");
+                    }
+                }
+                Err(err) => {
+                    error!("failed to encode screen: {err:?}");
+                    respond(request, 500, json!({ "error": "failed to encode screen" }));
+                }
+            }
+            return;
+        }
+        (Method::Get, "/screen.mjpeg") => {
+            // the stream blocks until the client goes away, so it gets a thread of its own
+
+            let screen = context.screen.clone();
+            let writer = request.into_writer();
+            thread::spawn(move || {
+                if let Err(err) = mirror::stream(writer, &screen) {
+                    debug!("screen stream ended: {err:?}");
+                }
+            });
+            return;
+        }
+        (Method::Post, "/view/next") => Command::Next,
+        (Method::Post, "/view/previous") => Command::Previous,
+        (Method::Post, "/sleep/toggle") => Command::ToggleSleep,
+        (Method::Post, "/message") => {
+            // read one byte more than allowed to also catch bodies without a length
+
+            let mut body = Vec::new();
+            let read = match request.body_length() {
+                Some(length) if length > MAX_MESSAGE_BODY => None,
+                _ => Some(
+                    request
+                        .as_reader()
+                        .take(MAX_MESSAGE_BODY as u64 + 1)
+                        .read_to_end(&mut body),
+                ),
+            };
+            let Some(read) = read.filter(|_| body.len() <= MAX_MESSAGE_BODY) else {
+                respond(request, 413, json!({ "error": "message too large" }));
+                return;
+            };
+            let message = read
+                .ok()
+                .and_then(|_| serde_json::from_slice::<MessageBody>(&body).ok());
+            match message {
+                Some(message) => Command::Message(message.text),
+                None => {
+                    let error = json!({ "error": "expected a body like {\"text\": \"...\"}" });
+                    respond(request, 400, error);
+                    return;
+                }
+            }
+        }
+        _ => {
+            respond(request, 404, json!({ "error": "not found" }));
+            return;
+        }
+    };
+
+    if context.commands.send(command).is_err() {
+        respond(request, 503, json!({ "error": "shutting down" }));
+        return;
+    }
+    respond(request, 202, json!({ "ok": true }));
+}
+
+/// Compares the tokens in constant time, so the time to reject one does not tell how much
+/// of it matched.
+fn same_token(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn respond(request: Request, code: u16, body: serde_json::Value) {
+    let header = Header::from_bytes("Content-Type", "application/json").expect("valid header");
+    let response = Response::from_string(body.to_string())
+        .with_status_code(code)
+        .with_header(header);
+    if let Err(err) = request.respond(response) {
+        warn!("failed to respond to control request: {err:?}");
+    }
+}
+
+impl Control {
+    /// Events for the commands received since the last call, the same the knob sends.
+    pub fn events(&self) -> Vec<Event> {
+        let mut sleep = self
+            .status
+            .lock()
+            .ok()
+            .and_then(|status| status.as_ref().map(|status| status.sleep))
+            .unwrap_or(false);
+
+        self.commands
+            .try_iter()
+            .map(|command| match command {
+                Command::Next => Event::NavigateDown,
+                Command::Previous => Event::NavigateUp,
+                Command::ToggleSleep => {
+                    sleep = !sleep;
+                    Event::SetSleep(sleep)
+                }
+                Command::Message(text) => Event::ShowMessage(text),
+            })
+            .collect()
+    }
+
+    /// Updates the screen served to clients, with the frame sent to the display.
+    pub fn set_frame(&self, frame: &Frame) {
+        self.screen.set(frame);
+    }
+
+    /// Updates the status served to clients.
+    pub fn set_status(&self, status: Status) {
+        if let Ok(mut current) = self.status.lock() {
+            *current = Some(status);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_tokens() {
+        assert!(same_token("secret", "secret"));
+        assert!(!same_token("secreT", "secret"));
+        assert!(!same_token("secret2", "secret"));
+        assert!(!same_token("", "secret"));
+    }
+}
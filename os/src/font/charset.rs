@@ -0,0 +1,11 @@
+/// Whether `c` can be drawn with the display fonts, which cover ISO 8859-15 (Latin-9).
+pub fn supports(c: char) -> bool {
+    match c {
+        ' '..='~' => true,
+        // Latin-9 replaces these Latin-1 symbols with the chars below
+        '¤' | '¦' | '¨' | '´' | '¸' | '¼' | '½' | '¾' => false,
+        '€' | 'Š' | 'š' | 'Ž' | 'ž' | 'Œ' | 'œ' | 'Ÿ' => true,
+        '\u{a0}'..='\u{ff}' => true,
+        _ => false,
+    }
+}
@@ -0,0 +1,72 @@
+mod charset;
+
+pub use charset::supports;
+pub use embedded_graphics::mono_font::iso_8859_15::{FONT_6X9, FONT_9X15_BOLD};
+
+// fonts converted from the BDF files in `fonts/` by `build.rs`
+include!(concat!(env!("OUT_DIR"), "/fonts.rs"));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::{Frame, HEIGHT, WIDTH};
+    use embedded_graphics::{
+        mono_font::{MonoFont, MonoTextStyle},
+        pixelcolor::BinaryColor,
+        prelude::*,
+        text::{Baseline, Text},
+    };
+
+    /// Top left and bottom right of the lit pixels of `text` drawn at the top left.
+    fn lit_bounds(font: &MonoFont, text: &str) -> Option<(Point, Point)> {
+        let mut frame = Frame::new();
+        Text::with_baseline(
+            text,
+            Point::zero(),
+            MonoTextStyle::new(font, BinaryColor::On),
+            Baseline::Top,
+        )
+        .draw(&mut frame)
+        .unwrap();
+
+        let lit = (0..HEIGHT as i32)
+            .flat_map(|y| (0..WIDTH as i32).map(move |x| Point::new(x, y)))
+            .filter(|p| frame.get(*p).is_on())
+            .collect::<Vec<_>>();
+        let min = lit.iter().copied().reduce(|a, b| a.component_min(b))?;
+        let max = lit.iter().copied().reduce(|a, b| a.component_max(b))?;
+        Some((min, max))
+    }
+
+    #[test]
+    fn seg7_digits_fill_their_cell() {
+        assert_eq!(FONT_SEG7_14X24.character_size, Size::new(14, 24));
+        assert_eq!(FONT_SEG7_14X24.character_spacing, 2);
+
+        assert_eq!(
+            lit_bounds(&FONT_SEG7_14X24, "8"),
+            Some((Point::new(0, 0), Point::new(13, 23)))
+        );
+        assert_eq!(
+            lit_bounds(&FONT_SEG7_14X24, "88"),
+            Some((Point::new(0, 0), Point::new(29, 23)))
+        );
+        assert_eq!(lit_bounds(&FONT_SEG7_14X24, " "), None);
+    }
+
+    #[test]
+    fn tiny_font_glyphs_stand_on_the_baseline() {
+        assert_eq!(FONT_4X6.character_size, Size::new(4, 6));
+        assert_eq!(FONT_4X6.character_spacing, 0);
+        assert_eq!(FONT_4X6.baseline, 4);
+
+        assert_eq!(
+            lit_bounds(&FONT_4X6, "H"),
+            Some((Point::new(0, 0), Point::new(2, 4)))
+        );
+        assert_eq!(
+            lit_bounds(&FONT_4X6, "HH"),
+            Some((Point::new(0, 0), Point::new(6, 4)))
+        );
+    }
+}
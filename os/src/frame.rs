@@ -0,0 +1,102 @@
+use anyhow::Result;
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
+use std::convert::Infallible;
+
+pub const WIDTH: u32 = 128;
+pub const HEIGHT: u32 = 64;
+
+/// Offscreen 128x64 framebuffer with the same size as the OLED.
+#[derive(Clone, PartialEq)]
+pub struct Frame {
+    pixels: Vec<BinaryColor>,
+}
+
+impl Frame {
+    pub fn new() -> Self {
+        Frame {
+            pixels: vec![BinaryColor::Off; (WIDTH * HEIGHT) as usize],
+        }
+    }
+
+    /// Color at `p`, pixels outside of the frame are off.
+    pub fn get(&self, p: Point) -> BinaryColor {
+        index(p).map(|i| self.pixels[i]).unwrap_or(BinaryColor::Off)
+    }
+
+    pub fn set(&mut self, p: Point, color: BinaryColor) {
+        if let Some(i) = index(p) {
+            self.pixels[i] = color;
+        }
+    }
+
+    /// Turns off a share of the lit pixels in an even pattern, so the simulator shows about
+    /// `percent` of the light.
+    pub fn dim(&mut self, percent: u8) {
+        if percent >= 100 {
+            return;
+        }
+
+        const BAYER: [[u32; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+        let level = (u32::from(percent) * 16 + 50) / 100;
+        for (i, pixel) in self.pixels.iter_mut().enumerate() {
+            let (x, y) = (i % WIDTH as usize, i / WIDTH as usize);
+            if BAYER[y % 4][x % 4] >= level {
+                *pixel = BinaryColor::Off;
+            }
+        }
+    }
+
+    /// One byte per pixel, row by row, 255 for lit pixels.
+    pub fn luma(&self) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .map(|pixel| if pixel.is_on() { 255 } else { 0 })
+            .collect()
+    }
+
+    /// Draws the whole frame onto `display`.
+    pub fn draw<D>(&self, display: &mut D) -> Result<()>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+        D::Error: Send + Sync + core::fmt::Debug + 'static,
+    {
+        display
+            .fill_contiguous(&self.bounding_box(), self.pixels.iter().copied())
+            .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))
+    }
+}
+
+fn index(p: Point) -> Option<usize> {
+    if p.x < 0 || p.y < 0 || p.x >= WIDTH as i32 || p.y >= HEIGHT as i32 {
+        return None;
+    }
+
+    Some(p.y as usize * WIDTH as usize + p.x as usize)
+}
+
+impl OriginDimensions for Frame {
+    fn size(&self) -> Size {
+        Size::new(WIDTH, HEIGHT)
+    }
+}
+
+impl DrawTarget for Frame {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(p, color) in pixels {
+            self.set(p, color);
+        }
+
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.pixels.fill(color);
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+use anyhow::Result;
+use embedded_graphics::{
+    image::{GetPixel, ImageRaw},
+    pixelcolor::BinaryColor,
+    prelude::*,
+};
+
+// icons converted from the PBM files in `assets/icons/` by `build.rs`
+include!(concat!(env!("OUT_DIR"), "/icons.rs"));
+
+/// Draws the set pixels of `icon` in `color` with its top-left corner at `at`, leaving the other
+/// pixels untouched so icons can be drawn on filled bars as well.
+pub fn draw<D>(
+    display: &mut D,
+    icon: &ImageRaw<BinaryColor>,
+    at: Point,
+    color: BinaryColor,
+) -> Result<()>
+where
+    D: DrawTarget<Color = BinaryColor>,
+    D::Error: Send + Sync + core::fmt::Debug + 'static,
+{
+    let pixels = icon
+        .bounding_box()
+        .points()
+        .filter(|p| icon.pixel(*p) == Some(BinaryColor::On))
+        .map(|p| Pixel(at + p, color));
+
+    display
+        .draw_iter(pixels)
+        .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))
+}
@@ -0,0 +1,202 @@
+use embedded_graphics::{mono_font::MonoFont, prelude::*};
+
+/// A single laid out line of text.
+#[derive(Debug, PartialEq)]
+pub enum Line {
+    Text(String),
+    /// A word wider than the line, shown as a scrolling ticker.
+    Ticker(String),
+}
+
+pub struct Layout {
+    pub font: &'static MonoFont<'static>,
+    pub columns: usize,
+    pub pages: Vec<Vec<Line>>,
+}
+
+/// Word-wraps `text` into `size`, using the first font in `fonts` that fits the whole text on a
+/// single page and paging with the last font otherwise. Always returns at least one page.
+pub fn layout(text: &str, fonts: &[&'static MonoFont<'static>], size: Size) -> Layout {
+    let mut result = None;
+    for font in fonts {
+        let columns = columns(font, size.width);
+        let rows = rows(font, size.height);
+        let lines = wrap(text, columns);
+        let fits = lines.len() <= rows;
+
+        result = Some(Layout {
+            font,
+            columns,
+            pages: paginate(lines, rows),
+        });
+        if fits {
+            break;
+        }
+    }
+
+    result.expect("at least one font")
+}
+
+pub fn columns(font: &MonoFont, width: u32) -> usize {
+    ((width + font.character_spacing) / (font.character_size.width + font.character_spacing)).max(1)
+        as usize
+}
+
+/// Width of `text` on a single line, without the spacing after the last char.
+pub fn width(font: &MonoFont, text: &str) -> u32 {
+    let chars = text.chars().count() as u32;
+    (chars * (font.character_size.width + font.character_spacing))
+        .saturating_sub(font.character_spacing)
+}
+
+pub fn rows(font: &MonoFont, height: u32) -> usize {
+    (height / font.character_size.height).max(1) as usize
+}
+
+pub fn wrap(text: &str, columns: usize) -> Vec<Line> {
+    let mut lines = Vec::new();
+
+    for paragraph in text.lines() {
+        let mut current = String::new();
+        let mut current_len = 0;
+
+        for word in paragraph.split_whitespace() {
+            let len = word.chars().count();
+
+            if len > columns {
+                if current_len > 0 {
+                    lines.push(Line::Text(std::mem::take(&mut current)));
+                    current_len = 0;
+                }
+                lines.push(Line::Ticker(word.to_string()));
+            } else if current_len == 0 {
+                current.push_str(word);
+                current_len = len;
+            } else if current_len + 1 + len <= columns {
+                current.push(' ');
+                current.push_str(word);
+                current_len += 1 + len;
+            } else {
+                lines.push(Line::Text(std::mem::replace(
+                    &mut current,
+                    word.to_string(),
+                )));
+                current_len = len;
+            }
+        }
+
+        if current_len > 0 {
+            lines.push(Line::Text(current));
+        }
+    }
+
+    lines
+}
+
+pub fn paginate(lines: Vec<Line>, rows: usize) -> Vec<Vec<Line>> {
+    let mut pages = vec![Vec::new()];
+    for line in lines {
+        if pages.last().is_some_and(|page| page.len() >= rows) {
+            pages.push(Vec::new());
+        }
+        if let Some(page) = pages.last_mut() {
+            page.push(line);
+        }
+    }
+
+    pages
+}
+
+/// The `columns` wide window of `word` starting at char `offset`.
+pub fn window(word: &str, offset: usize, columns: usize) -> String {
+    word.chars().skip(offset).take(columns).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font::{FONT_6X9, FONT_9X15_BOLD, FONT_SEG7_14X24};
+
+    fn text(line: &str) -> Line {
+        Line::Text(line.to_string())
+    }
+
+    fn ticker(word: &str) -> Line {
+        Line::Ticker(word.to_string())
+    }
+
+    #[test]
+    fn wraps_nothing_into_a_single_empty_page() {
+        assert!(wrap("", 10).is_empty());
+        assert!(wrap(" \n \n", 10).is_empty());
+
+        let layout = layout("", &[&FONT_9X15_BOLD, &FONT_6X9], Size::new(120, 46));
+        assert_eq!(layout.pages, [Vec::<Line>::new()]);
+    }
+
+    #[test]
+    fn fills_lines_up_to_the_last_column() {
+        assert_eq!(wrap("abc de", 6), [text("abc de")]);
+        assert_eq!(wrap("abc def", 6), [text("abc"), text("def")]);
+        assert_eq!(wrap("abcdef", 6), [text("abcdef")]);
+        assert_eq!(
+            wrap("one\n\ntwo  three", 9),
+            [text("one"), text("two three")]
+        );
+    }
+
+    #[test]
+    fn scrolls_words_longer_than_a_line() {
+        assert_eq!(
+            wrap("hi abcdefghijkl yo", 5),
+            [text("hi"), ticker("abcdefghijkl"), text("yo")]
+        );
+        assert_eq!(window("abcdefghijkl", 9, 5), "jkl");
+        assert_eq!(window("abcdefghijkl", 12, 5), "");
+    }
+
+    #[test]
+    fn counts_chars_not_bytes() {
+        assert_eq!(wrap("äöü ßé", 3), [text("äöü"), text("ßé")]);
+        assert_eq!(wrap("äöüß", 3), [ticker("äöüß")]);
+        assert_eq!(window("äöüß", 1, 2), "öü");
+    }
+
+    #[test]
+    fn pages_after_the_last_row() {
+        let lines = ["a", "b", "c", "d", "e"].map(text).into_iter().collect();
+        let pages = paginate(lines, 2);
+        assert_eq!(pages.iter().map(Vec::len).collect::<Vec<_>>(), [2, 2, 1]);
+        assert_eq!(paginate(Vec::new(), 3), [Vec::<Line>::new()]);
+    }
+
+    #[test]
+    fn falls_back_to_the_smaller_font() {
+        let fonts = [&FONT_9X15_BOLD, &FONT_6X9];
+        let size = Size::new(120, 46);
+
+        let short = layout("Dinner is ready", &fonts, size);
+        assert_eq!(short.columns, columns(&FONT_9X15_BOLD, 120));
+        assert_eq!(short.pages.len(), 1);
+
+        // four lines in the big font, but three in the small one
+        let medium = layout("Back home late tonight, please feed the cat", &fonts, size);
+        assert_eq!(medium.columns, columns(&FONT_6X9, 120));
+        assert_eq!(medium.pages.len(), 1);
+
+        let long = layout(&"word ".repeat(60), &fonts, size);
+        assert_eq!(long.columns, columns(&FONT_6X9, 120));
+        assert_eq!(
+            long.pages.len(),
+            wrap(&"word ".repeat(60), long.columns).len().div_ceil(5)
+        );
+        assert!(long.pages.len() > 1);
+    }
+
+    #[test]
+    fn measures_text_without_the_trailing_spacing() {
+        assert_eq!(width(&FONT_SEG7_14X24, "12:34"), 5 * 16 - 2);
+        assert_eq!(width(&FONT_9X15_BOLD, "0"), 9);
+        assert_eq!(width(&FONT_9X15_BOLD, ""), 0);
+    }
+}
@@ -1,4 +1,18 @@
+mod alarm;
+mod calendar;
+mod control;
+mod font;
+mod frame;
+mod icon;
+mod layout;
 mod message;
+mod mirror;
+mod mqtt;
+mod news;
+mod pomodoro;
+mod scheduler;
+mod storage;
+mod transit;
 mod weather;
 
 mod state;
@@ -1,18 +1,37 @@
+use crate::font;
 use anyhow::{Context, Result};
-use log::trace;
-use std::env;
+use log::{debug, info, trace, warn};
+use reqwest::blocking::RequestBuilder;
+use serde::Deserialize;
+use std::{
+    env,
+    io::{BufRead, BufReader},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::Duration,
+};
 
-pub fn fetch() -> Result<String> {
+/// The server sends a keep-alive every 15 seconds, so a connection silent for longer is dead.
+const PUSH_READ_TIMEOUT: Duration = Duration::from_secs(60);
+const PUSH_MIN_RETRY: Duration = Duration::from_secs(5);
+const PUSH_MAX_RETRY: Duration = Duration::from_secs(300);
+/// Device id used when `DEVICE_ID` is not set, the one the server uses for a single cube.
+const DEFAULT_DEVICE_ID: &str = "cube";
+
+pub struct Message {
+    pub text: String,
+    /// Opaque version the server attached to the message, echoed back in read receipts.
+    pub version: Option<String>,
+}
+
+pub fn fetch() -> Result<Message> {
     let url = env::var("MESSAGE_URL").context("MESSAGE_URL env var not set")?;
-    let username = env::var("MESSAGE_USERNAME").context("MESSAGE_USERNAME env var not set")?;
-    let password = env::var("MESSAGE_PASSWORD").context("MESSAGE_PASSWORD env var not set")?;
 
     trace!("fetching message from {}", url);
 
     let client = reqwest::blocking::Client::new();
-    let response = client
-        .get(&url)
-        .basic_auth(username, Some(password))
+    let response = authorize(client.get(&url))?
+        .header("X-Device-Id", device_id())
         .send()
         .context("failed to fetch message")?;
 
@@ -20,22 +39,180 @@ pub fn fetch() -> Result<String> {
         anyhow::bail!("failed to fetch message: status {}", response.status());
     }
 
+    let version = response
+        .headers()
+        .get("x-message-version")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
     let text = response.text().context("failed to read response text")?;
     trace!("fetched message: {}", text);
-    // replace chars that are not in the font with ?
-    let text = text
-        .chars()
+
+    Ok(Message {
+        text: sanitize(&text),
+        version,
+    })
+}
+
+/// Logs in to the message server with `MESSAGE_TOKEN`, or with `MESSAGE_USERNAME` and
+/// `MESSAGE_PASSWORD` when there is no token.
+pub fn authorize(request: RequestBuilder) -> Result<RequestBuilder> {
+    if let Ok(token) = env::var("MESSAGE_TOKEN")
+        && !token.is_empty()
+    {
+        return Ok(request.bearer_auth(token));
+    }
+
+    let username = env::var("MESSAGE_USERNAME").context("MESSAGE_USERNAME env var not set")?;
+    let password = env::var("MESSAGE_PASSWORD").context("MESSAGE_PASSWORD env var not set")?;
+    Ok(request.basic_auth(username, Some(password)))
+}
+
+/// Which inbox on the server to read, the server ignores it for devices that log in with
+/// their own credentials or token.
+fn device_id() -> String {
+    env::var("DEVICE_ID").unwrap_or(DEFAULT_DEVICE_ID.into())
+}
+
+/// Replaces chars that are not in the font with ?.
+fn sanitize(text: &str) -> String {
+    text.chars()
         .map(|c| {
-            if c.is_ascii_alphanumeric()
-                || c.is_ascii_whitespace()
-                || "!.,-#+?;:_'*/\"()".contains(c)
-            {
+            if font::supports(c) || c.is_ascii_whitespace() {
                 c
             } else {
                 '?'
             }
         })
-        .collect::<String>();
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// Messages pushed by the server as soon as they change, on top of polling with `fetch`.
+pub struct Subscription {
+    messages: Receiver<Message>,
+}
+
+#[derive(Deserialize)]
+struct PushedMessage {
+    text: String,
+    version: Option<String>,
+}
+
+/// Subscribes to the server-sent events at `<MESSAGE_URL>/events` in the background,
+/// unless `MESSAGE_PUSH` is `false`. The connection is retried with a growing delay while
+/// the server can't be reached.
+pub fn subscribe() -> Option<Subscription> {
+    let push = env::var("MESSAGE_PUSH")
+        .unwrap_or("true".into())
+        .parse::<bool>()
+        .unwrap_or(true);
+    if !push || env::var("MESSAGE_URL").is_err() {
+        return None;
+    }
+
+    let (sender, messages) = mpsc::channel();
+    thread::spawn(move || {
+        let mut retry = PUSH_MIN_RETRY;
+        loop {
+            match listen(&sender, &mut retry) {
+                Ok(()) => debug!("message push connection closed"),
+                Err(err) => warn!("message push failed: {err:?}"),
+            }
+            thread::sleep(retry);
+            retry = (retry * 2).min(PUSH_MAX_RETRY);
+        }
+    });
+
+    Some(Subscription { messages })
+}
+
+/// Reads pushed messages until the connection drops, resets `retry` once connected.
+fn listen(sender: &Sender<Message>, retry: &mut Duration) -> Result<()> {
+    let url = env::var("MESSAGE_URL").context("MESSAGE_URL env var not set")?;
+    let url = format!("{}/events", url.trim_end_matches('/'));
+
+    trace!("subscribing to messages at {}", url);
+
+    let client = reqwest::blocking::Client::builder()
+        .timeout(PUSH_READ_TIMEOUT)
+        .build()?;
+    let response = authorize(client.get(&url))?
+        .header("X-Device-Id", device_id())
+        .header("Accept", "text/event-stream")
+        .send()
+        .context("failed to subscribe to messages")?;
+
+    if !response.status().is_success() {
+        anyhow::bail!(
+            "failed to subscribe to messages: status {}",
+            response.status()
+        );
+    }
+    info!("subscribed to message updates");
+    *retry = PUSH_MIN_RETRY;
+
+    // events are `field: value` lines, ended by an empty line
+
+    let mut event = String::new();
+    let mut data = Vec::new();
+    for line in BufReader::new(response).lines() {
+        let line = line.context("failed to read pushed message")?;
+        if line.is_empty() {
+            if event == "message" && !data.is_empty() {
+                let pushed = serde_json::from_str::<PushedMessage>(&data.join("\n"))
+                    .context("failed to parse pushed message")?;
+                trace!("pushed message: {}", pushed.text);
+                let message = Message {
+                    text: sanitize(&pushed.text),
+                    version: pushed.version,
+                };
+                sender.send(message).context("message receiver gone")?;
+            }
+            event.clear();
+            data.clear();
+            continue;
+        }
+
+        let (field, value) = line.split_once(':').unwrap_or((&line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => event = value.to_string(),
+            "data" => data.push(value.to_string()),
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+impl Subscription {
+    /// Messages pushed since the last call.
+    pub fn messages(&self) -> Vec<Message> {
+        self.messages.try_iter().collect()
+    }
+}
+
+/// Tells the server that the message with `version` was shown on the display.
+pub fn report_seen(version: &str) -> Result<()> {
+    let url = env::var("MESSAGE_URL").context("MESSAGE_URL env var not set")?;
+    let url = format!("{}/seen", url.trim_end_matches('/'));
+
+    trace!("reporting seen message to {}", url);
+
+    let client = reqwest::blocking::Client::new();
+    let response = authorize(client.post(&url))?
+        .header("X-Device-Id", device_id())
+        .body(version.to_string())
+        .send()
+        .context("failed to report seen message")?;
+
+    if !response.status().is_success() {
+        anyhow::bail!(
+            "failed to report seen message: status {}",
+            response.status()
+        );
+    }
 
-    Ok(text.trim().to_string())
+    Ok(())
 }
@@ -0,0 +1,107 @@
+use crate::frame::{Frame, HEIGHT, WIDTH};
+use anyhow::Result;
+use log::debug;
+use std::{
+    io::Write,
+    sync::{Condvar, Mutex, PoisonError},
+    time::Duration,
+};
+
+const BOUNDARY: &str = "frame";
+
+/// Frames are sent again after a while without changes, so clients and proxies
+/// don't consider the stream dead.
+const KEEPALIVE: Duration = Duration::from_secs(10);
+
+/// The frame last sent to the display, whether that is the OLED or the simulator.
+pub struct Screen {
+    frame: Mutex<(u64, Frame)>,
+    changed: Condvar,
+}
+
+impl Screen {
+    /// A blank screen, as the display starts.
+    pub fn new() -> Self {
+        Screen {
+            frame: Mutex::new((0, Frame::new())),
+            changed: Condvar::new(),
+        }
+    }
+
+    pub fn set(&self, frame: &Frame) {
+        let mut current = self.frame.lock().unwrap_or_else(PoisonError::into_inner);
+        *current = (current.0 + 1, frame.clone());
+        self.changed.notify_all();
+    }
+
+    pub fn get(&self) -> Frame {
+        self.frame
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .1
+            .clone()
+    }
+
+    /// Waits for a frame newer than `version`, or returns the current one after the
+    /// keepalive.
+    fn wait(&self, version: Option<u64>) -> (u64, Frame) {
+        let current = self.frame.lock().unwrap_or_else(PoisonError::into_inner);
+        let (current, _) = self
+            .changed
+            .wait_timeout_while(current, KEEPALIVE, |current| Some(current.0) == version)
+            .unwrap_or_else(PoisonError::into_inner);
+        current.clone()
+    }
+}
+
+pub fn png(frame: &Frame) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    let mut encoder = png::Encoder::new(&mut data, WIDTH, HEIGHT);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&frame.luma())?;
+    writer.finish()?;
+    Ok(data)
+}
+
+pub fn jpeg(frame: &Frame) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    jpeg_encoder::Encoder::new(&mut data, 100).encode(
+        &frame.luma(),
+        WIDTH as u16,
+        HEIGHT as u16,
+        jpeg_encoder::ColorType::Luma,
+    )?;
+    Ok(data)
+}
+
+/// Writes a raw HTTP response to `writer` with an MJPEG stream of the screen, until
+/// the client goes away.
+pub fn stream(mut writer: impl Write, screen: &Screen) -> Result<()> {
+    write!(
+        writer,
+        "HTTP/1.1 200 OK\r\n\
+         Content-Type: multipart/x-mixed-replace; boundary={BOUNDARY}\r\n\
+         Cache-Control: no-cache\r\n\
+         Connection: close\r\n\r\n"
+    )?;
+    writer.flush()?;
+    debug!("start screen stream");
+
+    let mut version = None;
+    loop {
+        let (current, frame) = screen.wait(version);
+        version = Some(current);
+
+        let jpeg = jpeg(&frame)?;
+        write!(
+            writer,
+            "--{BOUNDARY}\r\nContent-Type: image/jpeg\r\nContent-Length: {}\r\n\r\n",
+            jpeg.len()
+        )?;
+        writer.write_all(&jpeg)?;
+        writer.write_all(b"\r\n")?;
+        writer.flush()?;
+    }
+}
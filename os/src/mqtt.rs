@@ -0,0 +1,300 @@
+use crate::state::{Event, View};
+use log::{debug, error, info, warn};
+use rumqttc::{Client, Event as MqttEvent, Incoming as Packet, LastWill, MqttOptions, QoS};
+use serde::Serialize;
+use serde_json::json;
+use std::{
+    env,
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::Duration,
+};
+
+/// What the cube shows, published retained to `<topic>/state` whenever it changes.
+#[derive(Clone, PartialEq, Serialize)]
+pub struct Status {
+    pub view: &'static str,
+    pub sleep: bool,
+    pub brightness: u8,
+    pub unread: bool,
+}
+
+enum Incoming {
+    Connected,
+    Command { topic: String, payload: String },
+}
+
+/// Connection to an MQTT broker, e.g. the one of Home Assistant. Publishes the status and
+/// knob input and turns commands into events.
+pub struct Mqtt {
+    client: Client,
+    topic: String,
+    incoming: Receiver<Incoming>,
+    published: Option<Status>,
+    republish: bool,
+}
+
+/// Connects to the broker at `MQTT_HOST` in the background, if set.
+pub fn connect() -> Option<Mqtt> {
+    let host = env::var("MQTT_HOST").ok().filter(|host| !host.is_empty())?;
+    let port = env::var("MQTT_PORT")
+        .unwrap_or("1883".into())
+        .parse::<u16>()
+        .unwrap_or(1883);
+    let topic = env::var("MQTT_TOPIC").unwrap_or("retro-cube".into());
+    let discovery = env::var("MQTT_DISCOVERY_PREFIX").unwrap_or("homeassistant".into());
+    info!("connect to mqtt broker {host}:{port} with topic {topic}");
+
+    let id = topic.replace('/', "_");
+    let mut options = MqttOptions::new(&id, host, port);
+    options.set_keep_alive(Duration::from_secs(30));
+    options.set_last_will(LastWill::new(
+        format!("{topic}/availability"),
+        "offline",
+        QoS::AtLeastOnce,
+        true,
+    ));
+    if let Ok(username) = env::var("MQTT_USERNAME") {
+        options.set_credentials(username, env::var("MQTT_PASSWORD").unwrap_or_default());
+    }
+
+    let (client, mut connection) = Client::new(options, 32);
+    let (sender, incoming) = mpsc::channel();
+
+    // the connection is driven in its own thread and reconnects on its own
+
+    let thread_client = client.clone();
+    let thread_topic = topic.clone();
+    thread::spawn(move || {
+        for notification in connection.iter() {
+            match notification {
+                Ok(MqttEvent::Incoming(Packet::ConnAck(_))) => {
+                    info!("connected to mqtt broker");
+                    // the requests queued while offline only drain once this thread polls
+                    // again, so a full queue would block the setup here forever
+                    let (client, topic, discovery, id) = (
+                        thread_client.clone(),
+                        thread_topic.clone(),
+                        discovery.clone(),
+                        id.clone(),
+                    );
+                    thread::spawn(move || setup(&client, &topic, &discovery, &id));
+                    if sender.send(Incoming::Connected).is_err() {
+                        return;
+                    }
+                }
+                Ok(MqttEvent::Incoming(Packet::Publish(publish))) => {
+                    let command = Incoming::Command {
+                        topic: publish.topic,
+                        payload: String::from_utf8_lossy(&publish.payload).into_owned(),
+                    };
+                    if sender.send(command).is_err() {
+                        return;
+                    }
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    error!("mqtt connection failed: {err:?}");
+                    thread::sleep(Duration::from_secs(5));
+                }
+            }
+        }
+    });
+
+    Some(Mqtt {
+        client,
+        topic,
+        incoming,
+        published: None,
+        republish: true,
+    })
+}
+
+/// Subscribes to the commands, announces the cube as online and to Home Assistant.
+fn setup(client: &Client, topic: &str, discovery: &str, id: &str) {
+    let mut messages = vec![(format!("{topic}/availability"), "online".to_string())];
+
+    if !discovery.is_empty() {
+        let device = json!({
+            "identifiers": [id],
+            "name": "Retro Cube",
+            "model": "retro-cube",
+        });
+        let entity = |component: &str, object: &str, name: &str, config: serde_json::Value| {
+            let mut payload = json!({
+                "name": name,
+                "unique_id": format!("{id}_{object}"),
+                "availability_topic": format!("{topic}/availability"),
+                "device": device,
+            });
+            if let (Some(payload), Some(config)) = (payload.as_object_mut(), config.as_object()) {
+                payload.extend(config.clone());
+            }
+            (
+                format!("{discovery}/{component}/{id}/{object}/config"),
+                payload.to_string(),
+            )
+        };
+
+        let views = View::ALL.iter().map(View::name).collect::<Vec<_>>();
+        messages.extend([
+            entity(
+                "select",
+                "view",
+                "View",
+                json!({
+                    "command_topic": format!("{topic}/view/set"),
+                    "state_topic": format!("{topic}/state"),
+                    "value_template": "{{ value_json.view }}",
+                    "options": views,
+                }),
+            ),
+            entity(
+                "switch",
+                "sleep",
+                "Night mode",
+                json!({
+                    "command_topic": format!("{topic}/sleep/set"),
+                    "state_topic": format!("{topic}/state"),
+                    "value_template": "{{ 'ON' if value_json.sleep else 'OFF' }}",
+                }),
+            ),
+            entity(
+                "number",
+                "brightness",
+                "Brightness",
+                json!({
+                    "command_topic": format!("{topic}/brightness/set"),
+                    "state_topic": format!("{topic}/state"),
+                    "value_template": "{{ value_json.brightness }}",
+                    "min": 0,
+                    "max": 100,
+                    "unit_of_measurement": "%",
+                }),
+            ),
+            entity(
+                "text",
+                "message",
+                "Message",
+                json!({
+                    "command_topic": format!("{topic}/message/set"),
+                    "max": 255,
+                }),
+            ),
+            entity(
+                "binary_sensor",
+                "unread",
+                "Unread message",
+                json!({
+                    "state_topic": format!("{topic}/state"),
+                    "value_template": "{{ 'ON' if value_json.unread else 'OFF' }}",
+                }),
+            ),
+        ]);
+
+        for (payload, trigger) in [
+            ("press", "button_short_press"),
+            ("long_press", "button_long_press"),
+        ] {
+            messages.push((
+                format!("{discovery}/device_automation/{id}/{payload}/config"),
+                json!({
+                    "automation_type": "trigger",
+                    "topic": format!("{topic}/button"),
+                    "payload": payload,
+                    "type": trigger,
+                    "subtype": "button_1",
+                    "device": device,
+                })
+                .to_string(),
+            ));
+        }
+    }
+
+    let subscribed = client.subscribe(format!("{topic}/+/set"), QoS::AtLeastOnce);
+    let published = messages
+        .into_iter()
+        .try_for_each(|(topic, payload)| client.publish(topic, QoS::AtLeastOnce, true, payload));
+    if let Err(err) = subscribed.and(published) {
+        error!("failed to set up mqtt: {err:?}");
+    }
+}
+
+impl Mqtt {
+    /// Events for the commands received since the last call.
+    pub fn events(&mut self) -> Vec<Event> {
+        let mut events = Vec::new();
+        while let Ok(incoming) = self.incoming.try_recv() {
+            let (topic, payload) = match incoming {
+                Incoming::Connected => {
+                    // publish the status again after a reconnect
+                    self.republish = true;
+                    continue;
+                }
+                Incoming::Command { topic, payload } => (topic, payload),
+            };
+            debug!("mqtt command {topic}: {payload}");
+
+            let command = topic
+                .strip_prefix(&format!("{}/", self.topic))
+                .and_then(|command| command.strip_suffix("/set"));
+            let payload = payload.trim();
+            let event = match command {
+                Some("message") => Some(Event::ShowMessage(payload.to_string())),
+                Some("view") => View::from_name(payload).map(Event::ShowView),
+                Some("sleep") => match payload.to_uppercase().as_str() {
+                    "ON" => Some(Event::SetSleep(true)),
+                    "OFF" => Some(Event::SetSleep(false)),
+                    "TOGGLE" => {
+                        let sleep = self.published.as_ref().is_some_and(|status| status.sleep);
+                        Some(Event::SetSleep(!sleep))
+                    }
+                    _ => None,
+                },
+                Some("brightness") => payload
+                    .parse::<f32>()
+                    .ok()
+                    .map(|percent| Event::SetBrightness(percent.clamp(0.0, 100.0) as u8)),
+                _ => None,
+            };
+
+            match event {
+                Some(event) => events.push(event),
+                None => warn!("ignore mqtt command {topic}: {payload}"),
+            }
+        }
+        events
+    }
+
+    /// Publishes the status if it changed since it was last published.
+    pub fn publish_status(&mut self, status: Status) {
+        if !self.republish && self.published.as_ref() == Some(&status) {
+            return;
+        }
+
+        let payload = json!(status).to_string();
+        match self.client.try_publish(
+            format!("{}/state", self.topic),
+            QoS::AtLeastOnce,
+            true,
+            payload,
+        ) {
+            Ok(()) => {
+                self.published = Some(status);
+                self.republish = false;
+            }
+            Err(err) => warn!("failed to publish status: {err:?}"),
+        }
+    }
+
+    /// Publishes knob input like `press` or `long_press` to `<topic>/button`.
+    pub fn publish_input(&self, input: &str) {
+        let topic = format!("{}/button", self.topic);
+        if let Err(err) = self
+            .client
+            .try_publish(topic, QoS::AtMostOnce, false, input)
+        {
+            warn!("failed to publish input: {err:?}");
+        }
+    }
+}
@@ -0,0 +1,284 @@
+use crate::font;
+use anyhow::{Context, Result};
+use log::{trace, warn};
+use std::{collections::HashSet, env};
+
+#[derive(Debug, Clone)]
+pub struct Headline {
+    pub title: String,
+    /// Title of the feed the headline is from.
+    pub source: String,
+}
+
+/// Whether any feed is configured in `NEWS_URLS`.
+pub fn is_configured() -> bool {
+    env::var("NEWS_URLS").is_ok_and(|urls| !urls.trim().is_empty())
+}
+
+/// Fetches the headlines of all RSS or Atom feeds in `NEWS_URLS`, taking turns between the
+/// feeds and skipping headlines that were seen already. Feeds that fail are left out, unless
+/// all of them do.
+pub fn fetch() -> Result<Vec<Headline>> {
+    let urls = env::var("NEWS_URLS").context("NEWS_URLS env var not set")?;
+    let max_headlines = env::var("NEWS_HEADLINES")
+        .unwrap_or("10".into())
+        .parse::<usize>()
+        .unwrap_or(10);
+
+    let mut feeds = Vec::new();
+    let mut failed = None;
+    for url in urls.split(',').map(str::trim).filter(|url| !url.is_empty()) {
+        trace!("fetching feed from {}", url);
+        let xml = reqwest::blocking::get(url)
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.text())
+            .with_context(|| format!("failed to fetch feed {url}"));
+        match xml {
+            Ok(xml) => feeds.push(parse(&xml)),
+            Err(err) => {
+                warn!("skip feed: {err:?}");
+                failed = Some(err);
+            }
+        }
+    }
+    if feeds.is_empty()
+        && let Some(err) = failed
+    {
+        return Err(err);
+    }
+
+    Ok(interleave(feeds, max_headlines))
+}
+
+/// Takes one headline of each feed in turn, up to `max_headlines`. The same story is often in
+/// several feeds, or twice in one, so titles seen already are skipped.
+fn interleave(feeds: Vec<Vec<Headline>>, max_headlines: usize) -> Vec<Headline> {
+    let mut feeds = feeds.into_iter().map(Vec::into_iter).collect::<Vec<_>>();
+    let mut seen = HashSet::new();
+    let mut headlines = Vec::new();
+    while headlines.len() < max_headlines {
+        let round = feeds
+            .iter_mut()
+            .filter_map(Iterator::next)
+            .collect::<Vec<_>>();
+        if round.is_empty() {
+            break;
+        }
+
+        for headline in round {
+            if seen.insert(headline.title.to_lowercase()) {
+                headlines.push(headline);
+            }
+        }
+    }
+    headlines.truncate(max_headlines);
+    headlines
+}
+
+/// Reads the item titles of an RSS feed or the entry titles of an Atom feed.
+pub fn parse(xml: &str) -> Vec<Headline> {
+    let (tag, start) = match (xml.find("<item"), xml.find("<entry")) {
+        (Some(item), _) => ("item", item),
+        (None, Some(entry)) => ("entry", entry),
+        (None, None) => return Vec::new(),
+    };
+    let source = element(&xml[..start], "title").unwrap_or_default();
+
+    let mut headlines = Vec::new();
+    let mut rest = &xml[start..];
+    while let Some(item) = element_raw(rest, tag) {
+        if let Some(title) = element(item, "title").filter(|title| !title.is_empty()) {
+            headlines.push(Headline {
+                title,
+                source: source.clone(),
+            });
+        }
+
+        let end = format!("</{tag}>");
+        let Some(next) = rest.find(&end) else {
+            break;
+        };
+        rest = &rest[next + end.len()..];
+    }
+
+    headlines
+}
+
+/// Text of the first element named `tag`, without markup.
+fn element(xml: &str, tag: &str) -> Option<String> {
+    element_raw(xml, tag).map(text)
+}
+
+/// Raw content of the first element named `tag`.
+fn element_raw<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{tag}");
+    let mut from = 0;
+    let start = loop {
+        let start = from + xml[from..].find(&open)?;
+        let after = &xml[start + open.len()..];
+
+        // skip longer names like `<items>` for `<item`
+        match after.chars().next() {
+            Some('>' | ' ' | '\t' | '\r' | '\n' | '/') => break start,
+            _ => from = start + open.len(),
+        }
+    };
+
+    let content_start = start + xml[start..].find('>')? + 1;
+    if xml[..content_start].ends_with("/>") {
+        return Some("");
+    }
+    let content_end = content_start + xml[content_start..].find(&format!("</{tag}>"))?;
+    Some(&xml[content_start..content_end])
+}
+
+/// Unwraps CDATA, decodes entities, drops HTML tags and replaces typographic punctuation and
+/// other chars the fonts can't show.
+fn text(raw: &str) -> String {
+    let raw = raw.trim();
+    let raw = raw
+        .strip_prefix("<![CDATA[")
+        .and_then(|raw| raw.strip_suffix("]]>"))
+        .map_or_else(|| decode(raw), str::to_string);
+
+    // Atom titles of type html carry escaped markup, which is decoded above
+
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in raw.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if in_tag => {}
+            '\u{2013}' | '\u{2014}' => text.push('-'),
+            '\u{2018}' | '\u{2019}' => text.push('\''),
+            '\u{201c}' | '\u{201d}' => text.push('"'),
+            '\u{2026}' => text.push_str("..."),
+            c if font::supports(c) => text.push(c),
+            c if c.is_whitespace() => text.push(' '),
+            _ => text.push('?'),
+        }
+    }
+
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn decode(raw: &str) -> String {
+    let mut decoded = String::new();
+    let mut rest = raw;
+    while let Some(amp) = rest.find('&') {
+        decoded.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        let entity = rest
+            .find(';')
+            .filter(|end| *end <= 10)
+            .map(|end| (&rest[1..end], end));
+        let Some((name, end)) = entity else {
+            decoded.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+
+        let c = match name {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => name
+                .strip_prefix("#x")
+                .or_else(|| name.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| name.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match c {
+            Some(c) => {
+                decoded.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titles(headlines: &[Headline]) -> Vec<&str> {
+        headlines
+            .iter()
+            .map(|headline| headline.title.as_str())
+            .collect()
+    }
+
+    fn feed(source: &str, titles: &[&str]) -> Vec<Headline> {
+        titles
+            .iter()
+            .map(|title| Headline {
+                title: title.to_string(),
+                source: source.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parses_rss() {
+        let headlines = parse(include_str!("../fixtures/news/rss.xml"));
+
+        assert_eq!(
+            titles(&headlines),
+            [
+                "Storm \"Zoltan\" hits the coast - ferries cancelled",
+                "Prices up 3% & still rising...",
+                "Local team wins the cup",
+            ]
+        );
+        assert!(headlines.iter().all(|h| h.source == "Example & News"));
+    }
+
+    #[test]
+    fn parses_atom() {
+        let headlines = parse(include_str!("../fixtures/news/atom.xml"));
+
+        assert_eq!(
+            titles(&headlines),
+            ["The new edition is out", "It's \"done\""]
+        );
+        assert!(headlines.iter().all(|h| h.source == "Dev Blog"));
+    }
+
+    #[test]
+    fn skips_the_items_list_of_rss_1() {
+        let headlines = parse(include_str!("../fixtures/news/rdf.xml"));
+
+        assert_eq!(titles(&headlines), ["First post", "Second post"]);
+        assert!(headlines.iter().all(|h| h.source == "Planet"));
+    }
+
+    #[test]
+    fn takes_turns_between_feeds_without_repeats() {
+        let feeds = vec![
+            feed("a", &["Storm", "Election", "Storm", "Sports"]),
+            feed("b", &["STORM", "Weather"]),
+            feed("c", &[]),
+        ];
+
+        let headlines = interleave(feeds.clone(), 10);
+        assert_eq!(
+            titles(&headlines),
+            ["Storm", "Election", "Weather", "Sports"]
+        );
+        assert_eq!(headlines[0].source, "a");
+
+        assert_eq!(titles(&interleave(feeds, 2)), ["Storm", "Election"]);
+    }
+}
@@ -1,15 +1,25 @@
-use crate::state;
+use crate::{
+    frame::{HEIGHT, WIDTH},
+    scheduler::Scheduler,
+    state,
+};
 use anyhow::Result;
 use display_interface_spi::SPIInterface;
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
 use log::{info, trace};
 use rppal::{
-    gpio::{Gpio, Level},
+    gpio::{Gpio, Level, OutputPin},
     spi::{Bus, Mode, SlaveSelect, Spi},
 };
 use ssd1309::{
-    self, builder::Builder, displayrotation::DisplayRotation, mode::graphics::GraphicsMode,
+    self,
+    builder::Builder,
+    displayrotation::DisplayRotation,
+    mode::{displaymode::DisplayModeTrait, graphics::GraphicsMode},
+    properties::DisplayProperties,
 };
 use std::{
+    convert::Infallible,
     sync::{
         Arc,
         atomic::{AtomicBool, Ordering},
@@ -18,6 +28,11 @@ use std::{
     time::{Duration, Instant},
 };
 
+/// Holding the knob down this long is a long press.
+const LONG_PRESS: Duration = Duration::from_millis(1000);
+/// The knob is polled, so wake up this often between frames to not miss its steps.
+const INPUT_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
 pub fn render() -> Result<()> {
     // map LED pin
 
@@ -36,6 +51,16 @@ pub fn render() -> Result<()> {
     let rotary_dt = Gpio::new()?.get(15)?.into_input();
     let rotary_sw = Gpio::new()?.get(14)?.into_input_pullup();
 
+    // map the optional alarm buzzer pin
+
+    let mut buzzer = match std::env::var("ALARM_BUZZER_PIN")
+        .ok()
+        .and_then(|pin| pin.parse::<u8>().ok())
+    {
+        Some(pin) => Some(Gpio::new()?.get(pin)?.into_output_low()),
+        None => None,
+    };
+
     // map OLED pins
 
     let dc = Gpio::new()?.get(2)?.into_output();
@@ -65,6 +90,10 @@ pub fn render() -> Result<()> {
 
     display.init().expect("Failed to initialize display");
 
+    // take over the initialized display to only send changed pages
+
+    let mut display = Pages::new(display.release());
+
     // setup trigger for exit on ctrl-c
 
     let running = Arc::new(AtomicBool::new(true));
@@ -79,8 +108,11 @@ pub fn render() -> Result<()> {
     // initialize state
 
     let mut state = state::State::new();
+    let mut scheduler = Scheduler::from_env();
     let mut last_rotary_clk_state = rotary_clk.read();
     let mut last_rotary_sw_state = rotary_sw.read();
+    let mut pressed_at: Option<Instant> = None;
+    let mut contrast = None;
 
     // render state and handle events
 
@@ -88,47 +120,182 @@ pub fn render() -> Result<()> {
     while running.load(Ordering::SeqCst) {
         trace!("main loop tick");
 
-        // detect rotary events
+        // detect rotary events, turning while the button is held cancels its press
 
         let clk_state = rotary_clk.read();
         let dt_state = rotary_dt.read();
+        let held = last_rotary_sw_state == Level::Low;
         if last_nav_at.elapsed().as_millis() > 144 {
             if clk_state != last_rotary_clk_state {
+                if held {
+                    pressed_at = None;
+                }
                 if dt_state != clk_state {
                     info!("navigate ->");
-                    state.update(&mut display, state::Event::NavigateUp);
+                    state.update(
+                        &mut display,
+                        if held {
+                            state::Event::PressedNavigateUp
+                        } else {
+                            state::Event::NavigateUp
+                        },
+                    );
                     last_nav_at = Instant::now();
                 } else {
                     info!("navigate <-");
-                    state.update(&mut display, state::Event::NavigateDown);
+                    state.update(
+                        &mut display,
+                        if held {
+                            state::Event::PressedNavigateDown
+                        } else {
+                            state::Event::NavigateDown
+                        },
+                    );
                     last_nav_at = Instant::now();
                 }
             }
         }
         last_rotary_clk_state = clk_state;
 
-        // detect rotary button
+        // detect rotary button, a press is sent on release unless it was held long enough
 
         let sw_state = rotary_sw.read();
-        if sw_state != last_rotary_sw_state
-            && sw_state == Level::Low
-            && last_nav_at.elapsed().as_millis() > 144
-        {
-            state.update(&mut display, state::Event::ToggleSleep);
+        if sw_state != last_rotary_sw_state {
+            if sw_state == Level::Low && last_nav_at.elapsed().as_millis() > 144 {
+                pressed_at = Some(Instant::now());
+            } else if sw_state == Level::High && pressed_at.take().is_some() {
+                state.update(&mut display, state::Event::Press);
+            }
+        }
+        if pressed_at.is_some_and(|at| at.elapsed() >= LONG_PRESS) {
+            pressed_at = None;
+            info!("long press");
+            state.update(&mut display, state::Event::LongPress);
         }
         last_rotary_sw_state = sw_state;
 
-        // render state
+        // render state at the target frame rate, poll input in between
+
+        if scheduler.due() {
+            state.update(&mut display, state::Event::Tick);
+            display.flush()?;
 
-        display.clear();
-        state.update(&mut display, state::Event::Tick);
-        display.flush().expect("fails to flush");
+            if let Some(buzzer) = &mut buzzer {
+                buzzer.write(if state.buzzing() {
+                    Level::High
+                } else {
+                    Level::Low
+                });
+            }
+            if contrast != Some(state.brightness()) {
+                display.set_contrast(state.brightness())?;
+                contrast = Some(state.brightness());
+            }
+        }
+        sleep(scheduler.until_next().min(INPUT_POLL_INTERVAL));
     }
 
-    // clear display
+    // silence buzzer and clear display
 
-    display.clear();
-    display.flush().expect("fails to flush");
+    if let Some(buzzer) = &mut buzzer {
+        buzzer.set_low();
+    }
+
+    display.clear(BinaryColor::Off)?;
+    display.flush()?;
 
     Ok(())
 }
+
+type Interface = SPIInterface<Spi, OutputPin, OutputPin>;
+
+const PAGES: usize = (HEIGHT / 8) as usize;
+
+/// Framebuffer in the SSD1309's memory layout, one byte holds 8 vertical pixels of a page.
+/// Flushing only sends the columns of each page that changed since the last flush.
+struct Pages {
+    properties: DisplayProperties<Interface>,
+    buffer: [u8; WIDTH as usize * PAGES],
+    shown: Option<[u8; WIDTH as usize * PAGES]>,
+}
+
+impl Pages {
+    fn new(properties: DisplayProperties<Interface>) -> Self {
+        Pages {
+            properties,
+            buffer: [0; WIDTH as usize * PAGES],
+            shown: None,
+        }
+    }
+
+    /// Sets the contrast to `percent` of the maximum, which dims the whole panel evenly.
+    fn set_contrast(&mut self, percent: u8) -> Result<()> {
+        let contrast = u16::from(percent.min(100)) * 255 / 100;
+        self.properties
+            .set_contrast(contrast as u8)
+            .map_err(|e| anyhow::anyhow!("set contrast failed: {:?}", e))
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        let width = WIDTH as usize;
+
+        for page in 0..PAGES {
+            let columns = &self.buffer[page * width..(page + 1) * width];
+            let shown = self
+                .shown
+                .as_ref()
+                .map(|shown| &shown[page * width..(page + 1) * width]);
+
+            // range of columns that differ from what the display shows
+
+            let changed = |x: &usize| shown.is_none_or(|shown| shown[*x] != columns[*x]);
+            let Some(first) = (0..width).find(changed) else {
+                continue;
+            };
+            let last = (0..width).rfind(changed).unwrap_or(first);
+
+            trace!("flush page {page}, columns {first}..={last}");
+            let top = (page * 8) as u8;
+            self.properties
+                .set_draw_area((first as u8, top), (last as u8 + 1, top + 8))
+                .map_err(|e| anyhow::anyhow!("set draw area failed: {:?}", e))?;
+            self.properties
+                .draw(&columns[first..=last])
+                .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+        }
+
+        self.shown = Some(self.buffer);
+        Ok(())
+    }
+}
+
+impl OriginDimensions for Pages {
+    fn size(&self) -> Size {
+        Size::new(WIDTH, HEIGHT)
+    }
+}
+
+impl DrawTarget for Pages {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(p, color) in pixels {
+            if p.x < 0 || p.y < 0 || p.x >= WIDTH as i32 || p.y >= HEIGHT as i32 {
+                continue;
+            }
+
+            let index = (p.y as usize / 8) * WIDTH as usize + p.x as usize;
+            let bit = 1 << (p.y % 8);
+            match color {
+                BinaryColor::On => self.buffer[index] |= bit,
+                BinaryColor::Off => self.buffer[index] &= !bit,
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use crate::message;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use log::trace;
+use serde::{Deserialize, Serialize};
+use std::env;
+
+/// A finished or aborted focus block.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub started_at: DateTime<Utc>,
+    /// Minutes actually focused, shorter than planned if the block was reset.
+    pub minutes: i64,
+    pub completed: bool,
+}
+
+/// Reports focus sessions to the message server for its weekly summary.
+pub fn report(sessions: &[Session]) -> Result<()> {
+    let url = env::var("MESSAGE_URL").context("MESSAGE_URL env var not set")?;
+    let url = format!("{}/pomodoro", url.trim_end_matches('/'));
+
+    trace!("reporting {} focus sessions to {}", sessions.len(), url);
+
+    let client = reqwest::blocking::Client::new();
+    let response = message::authorize(client.post(&url))?
+        .json(sessions)
+        .send()
+        .context("failed to report focus sessions")?;
+
+    if !response.status().is_success() {
+        anyhow::bail!(
+            "failed to report focus sessions: status {}",
+            response.status()
+        );
+    }
+
+    Ok(())
+}
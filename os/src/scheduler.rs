@@ -0,0 +1,40 @@
+use log::info;
+use std::time::{Duration, Instant};
+
+/// Paces rendering to a target frame rate, independent of how often the input is polled.
+pub struct Scheduler {
+    interval: Duration,
+    next: Instant,
+}
+
+impl Scheduler {
+    pub fn from_env() -> Self {
+        let fps = std::env::var("FPS")
+            .unwrap_or("20".into())
+            .parse::<u32>()
+            .unwrap_or(20)
+            .clamp(1, 60);
+        info!("render with {fps} fps");
+
+        Scheduler {
+            interval: Duration::from_secs(1) / fps,
+            next: Instant::now(),
+        }
+    }
+
+    /// Whether the next frame is due, schedules the one after it if so.
+    pub fn due(&mut self) -> bool {
+        let now = Instant::now();
+        if now < self.next {
+            return false;
+        }
+
+        // skip frames that were missed instead of rendering them all at once
+        self.next = (self.next + self.interval).max(now);
+        true
+    }
+
+    pub fn until_next(&self) -> Duration {
+        self.next.saturating_duration_since(Instant::now())
+    }
+}
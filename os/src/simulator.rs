@@ -1,8 +1,7 @@
-use crate::state;
+use crate::{scheduler::Scheduler, state};
 
 use anyhow::Result;
 use embedded_graphics::{
-    draw_target::DrawTarget,
     pixelcolor::{BinaryColor, Rgb888},
     prelude::*,
 };
@@ -44,14 +43,16 @@ pub fn render() -> Result<()> {
     // initialize state
 
     let mut state = state::State::new();
+    let mut scheduler = Scheduler::from_env();
 
     // render state and handle events
 
     while running.load(Ordering::SeqCst) {
-        display.clear(BinaryColor::Off);
-
-        state.update(&mut display, Event::Tick);
-        window.update(&display);
+        std::thread::sleep(scheduler.until_next());
+        if scheduler.due() {
+            state.update(&mut display, Event::Tick);
+            window.update(&display);
+        }
 
         for event in window.events() {
             match event {
@@ -60,7 +61,10 @@ pub fn render() -> Result<()> {
                     match keycode {
                         Keycode::Up => state.update(&mut display, Event::NavigateUp),
                         Keycode::Down => state.update(&mut display, Event::NavigateDown),
-                        Keycode::RETURN => state.update(&mut display, Event::ToggleSleep),
+                        Keycode::Left => state.update(&mut display, Event::PressedNavigateUp),
+                        Keycode::Right => state.update(&mut display, Event::PressedNavigateDown),
+                        Keycode::RETURN => state.update(&mut display, Event::Press),
+                        Keycode::Backspace => state.update(&mut display, Event::LongPress),
                         _ => {}
                     };
                 }
@@ -0,0 +1,119 @@
+use crate::{
+    calendar::{self, Event},
+    storage,
+};
+use anyhow::Result;
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use chrono_tz::Tz;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+
+const STORAGE_KEY: &str = "agenda";
+
+/// Rows of the Agenda view per page.
+pub const ROWS_PER_PAGE: usize = 5;
+
+pub enum Row {
+    Day(&'static str),
+    Event(String),
+}
+
+/// Events of today and tomorrow, cached so the agenda survives restarts and failed fetches.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Agenda {
+    pub events: Vec<Event>,
+    pub fetched_at: Option<DateTime<Utc>>,
+}
+
+impl Agenda {
+    pub fn load() -> Self {
+        storage::load(STORAGE_KEY).unwrap_or_default()
+    }
+
+    /// Fetches the events from the start of today until the end of tomorrow in `timezone`.
+    pub fn fetch(&mut self, timezone: Tz) -> Result<()> {
+        let now = Utc::now();
+        let today = now.with_timezone(&timezone).date_naive();
+        let events = calendar::fetch(
+            start_of(today, timezone),
+            start_of(today + Days::new(2), timezone),
+            timezone,
+        )?;
+
+        if events != self.events {
+            info!("received {} calendar events", events.len());
+        }
+        self.events = events;
+        self.fetched_at = Some(now);
+        self.save();
+        Ok(())
+    }
+
+    /// Rows of the Agenda view, timed events that are over already are left out.
+    pub fn rows(&self, now: DateTime<Utc>, timezone: Tz) -> Vec<Row> {
+        let today = now.with_timezone(&timezone).date_naive();
+
+        let mut rows = Vec::new();
+        for (title, day) in [("TODAY", today), ("TOMORROW", today + Days::new(1))] {
+            let (from, to) = (
+                start_of(day, timezone),
+                start_of(day + Days::new(1), timezone),
+            );
+            rows.push(Row::Day(title));
+
+            let events = self.events.iter().filter(|event| {
+                if event.all_day {
+                    event.start < to && event.end > from
+                } else {
+                    (from..to).contains(&event.start) && event.end > now
+                }
+            });
+
+            let count = rows.len();
+            for event in events {
+                let time = if event.all_day {
+                    "all".to_string()
+                } else {
+                    event
+                        .start
+                        .with_timezone(&timezone)
+                        .format("%H:%M")
+                        .to_string()
+                };
+                rows.push(Row::Event(format!("{time:<5} {}", event.summary)));
+            }
+            if rows.len() == count {
+                rows.push(Row::Event("No events.".to_string()));
+            }
+        }
+        rows
+    }
+
+    pub fn pages(&self, now: DateTime<Utc>, timezone: Tz) -> usize {
+        self.rows(now, timezone).len().div_ceil(ROWS_PER_PAGE)
+    }
+
+    /// Next timed event starting later today.
+    pub fn next(&self, now: DateTime<Utc>, timezone: Tz) -> Option<&Event> {
+        let tomorrow = now.with_timezone(&timezone).date_naive() + Days::new(1);
+        self.events
+            .iter()
+            .filter(|event| !event.all_day && event.start > now)
+            .find(|event| event.start < start_of(tomorrow, timezone))
+    }
+
+    fn save(&self) {
+        if let Err(err) = storage::save(STORAGE_KEY, self) {
+            error!("failed to save agenda: {err:?}");
+        }
+    }
+}
+
+/// Midnight of `day` in `timezone`.
+fn start_of(day: NaiveDate, timezone: Tz) -> DateTime<Utc> {
+    day.and_hms_opt(0, 0, 0)
+        .and_then(|midnight| midnight.and_local_timezone(timezone).earliest())
+        .map_or(day.and_time(Default::default()).and_utc(), |midnight| {
+            midnight.with_timezone(&Utc)
+        })
+}
@@ -0,0 +1,279 @@
+use crate::{
+    alarm::{self, Alarm},
+    storage,
+};
+use chrono::{DateTime, Duration, NaiveDateTime, Timelike, Utc};
+use chrono_tz::Tz;
+use log::{error, info};
+
+const LOCAL_KEY: &str = "alarms";
+const REMOTE_KEY: &str = "alarms_remote";
+
+/// An alarm stops ringing on its own after this many minutes.
+const RING_MINUTES: i64 = 10;
+
+/// Alarms set on the device and on the server, and whether one of them is ringing.
+pub struct Alarms {
+    pub local: Vec<Alarm>,
+    pub remote: Vec<Alarm>,
+    pub editor: Option<Editor>,
+
+    snooze_minutes: i64,
+    last_check: Option<NaiveDateTime>,
+    ringing_since: Option<DateTime<Utc>>,
+    snoozed_until: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Field {
+    Select,
+    Hour,
+    Minute,
+    Repeat,
+    Status,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Status {
+    On,
+    Off,
+    Delete,
+}
+
+/// On-device editor for the local alarms, `index == local.len()` creates a new one.
+pub struct Editor {
+    pub index: usize,
+    pub field: Field,
+    pub draft: Alarm,
+    pub status: Status,
+}
+
+impl Alarms {
+    pub fn load() -> Self {
+        let snooze_minutes = std::env::var("ALARM_SNOOZE_MINUTES")
+            .unwrap_or("9".into())
+            .parse::<i64>()
+            .unwrap_or(9);
+
+        Alarms {
+            local: storage::load(LOCAL_KEY).unwrap_or_default(),
+            remote: storage::load(REMOTE_KEY).unwrap_or_default(),
+            editor: None,
+
+            snooze_minutes,
+            last_check: None,
+            ringing_since: None,
+            snoozed_until: None,
+        }
+    }
+
+    pub fn set_remote(&mut self, alarms: Vec<Alarm>) {
+        if self.remote == alarms {
+            return;
+        }
+
+        info!("received {} alarms from the server", alarms.len());
+        self.remote = alarms;
+        if let Err(err) = storage::save(REMOTE_KEY, &self.remote) {
+            error!("failed to save alarms: {err:?}");
+        }
+    }
+
+    /// Starts ringing when an alarm is due or a snooze is over, returns true if it started.
+    pub fn check(&mut self, now: DateTime<Utc>, timezone: Tz) -> bool {
+        if let Some(since) = self.ringing_since
+            && now - since > Duration::minutes(RING_MINUTES)
+        {
+            info!("alarm timed out");
+            self.dismiss();
+        }
+
+        if let Some(until) = self.snoozed_until
+            && now >= until
+        {
+            info!("snooze is over");
+            return self.ring(now);
+        }
+
+        // alarms ring at full minutes, check each minute once
+
+        let local = now.with_timezone(&timezone).naive_local();
+        let minute = local
+            .with_second(0)
+            .and_then(|t| t.with_nanosecond(0))
+            .unwrap_or(local);
+        if self.last_check == Some(minute) {
+            return false;
+        }
+        self.last_check = Some(minute);
+
+        let due = self
+            .local
+            .iter()
+            .chain(self.remote.iter())
+            .any(|alarm| alarm.rings_at(minute));
+        if !due {
+            return false;
+        }
+
+        // one-off alarms set on the device are disabled once they rang
+
+        let mut changed = false;
+        for alarm in self.local.iter_mut() {
+            if alarm.rings_at(minute) && !alarm.is_repeating() {
+                alarm.enabled = false;
+                changed = true;
+            }
+        }
+        if changed {
+            self.save();
+        }
+
+        info!("alarm at {}", minute.format("%H:%M"));
+        self.ring(now)
+    }
+
+    fn ring(&mut self, now: DateTime<Utc>) -> bool {
+        self.snoozed_until = None;
+        if self.ringing_since.is_some() {
+            return false;
+        }
+
+        self.ringing_since = Some(now);
+        true
+    }
+
+    pub fn is_ringing(&self) -> bool {
+        self.ringing_since.is_some()
+    }
+
+    pub fn is_snoozed(&self) -> bool {
+        self.snoozed_until.is_some()
+    }
+
+    pub fn snooze(&mut self) {
+        info!("snooze alarm for {} minutes", self.snooze_minutes);
+        self.ringing_since = None;
+        self.snoozed_until = Some(Utc::now() + Duration::minutes(self.snooze_minutes));
+    }
+
+    pub fn dismiss(&mut self) {
+        info!("dismiss alarm");
+        self.ringing_since = None;
+        self.snoozed_until = None;
+    }
+
+    // editor
+
+    pub fn start_editing(&mut self) {
+        self.editor = Some(Editor::select(&self.local, 0));
+    }
+
+    pub fn finish_editing(&mut self) {
+        // on the selection nothing was edited yet
+        if self
+            .editor
+            .as_ref()
+            .is_some_and(|e| e.field != Field::Select)
+        {
+            self.commit();
+        }
+        self.editor = None;
+    }
+
+    /// Changes the value of the field being edited by `delta` steps.
+    pub fn turn(&mut self, delta: i32) {
+        let Some(editor) = self.editor.as_mut() else {
+            return;
+        };
+
+        let wrap = |value: i32, len: i32| (value + delta).rem_euclid(len);
+        match editor.field {
+            Field::Select => {
+                let index = wrap(editor.index as i32, self.local.len() as i32 + 1) as usize;
+                *editor = Editor::select(&self.local, index);
+            }
+            Field::Hour => editor.draft.hour = wrap(editor.draft.hour as i32, 24) as u32,
+            Field::Minute => {
+                // five minute steps, so setting a time does not take forever
+                let step = editor.draft.minute as i32 / 5;
+                editor.draft.minute = wrap(step, 12) as u32 * 5;
+            }
+            Field::Repeat => {
+                let presets = alarm::PRESETS.len() as i32;
+                let current = alarm::PRESETS
+                    .iter()
+                    .position(|preset| editor.draft.has_days(preset))
+                    .map_or(if delta > 0 { -1 } else { 0 }, |n| n as i32);
+                editor.draft.days = alarm::PRESETS[wrap(current, presets) as usize].to_vec();
+                editor.draft.date = None;
+            }
+            Field::Status => {
+                let statuses = [Status::On, Status::Off, Status::Delete];
+                let current = statuses.iter().position(|s| *s == editor.status);
+                editor.status = statuses[wrap(current.unwrap_or(0) as i32, 3) as usize];
+            }
+        }
+    }
+
+    /// Moves on to the next field, saving the alarm after its last field.
+    pub fn next_field(&mut self) {
+        let Some(editor) = self.editor.as_mut() else {
+            return;
+        };
+
+        editor.field = match editor.field {
+            Field::Select => Field::Hour,
+            Field::Hour => Field::Minute,
+            Field::Minute => Field::Repeat,
+            Field::Repeat => Field::Status,
+            Field::Status => return self.commit(),
+        };
+    }
+
+    /// Saves the draft and goes back to selecting an alarm.
+    fn commit(&mut self) {
+        let Some(editor) = self.editor.as_mut() else {
+            return;
+        };
+
+        let mut alarm = editor.draft.clone();
+        alarm.enabled = editor.status == Status::On;
+        match (editor.status, editor.index < self.local.len()) {
+            (Status::Delete, true) => {
+                self.local.remove(editor.index);
+            }
+            (Status::Delete, false) => {}
+            (_, true) => self.local[editor.index] = alarm,
+            (_, false) => self.local.push(alarm),
+        }
+
+        let index = editor.index.min(self.local.len());
+        *editor = Editor::select(&self.local, index);
+        self.save();
+    }
+
+    fn save(&self) {
+        if let Err(err) = storage::save(LOCAL_KEY, &self.local) {
+            error!("failed to save alarms: {err:?}");
+        }
+    }
+}
+
+impl Editor {
+    fn select(alarms: &[Alarm], index: usize) -> Self {
+        let draft = alarms.get(index).cloned().unwrap_or_default();
+        let status = if draft.enabled {
+            Status::On
+        } else {
+            Status::Off
+        };
+
+        Editor {
+            index,
+            field: Field::Select,
+            draft,
+            status,
+        }
+    }
+}
@@ -1,6 +1,37 @@
+use super::View;
+
 pub enum Event {
     Tick,
     NavigateUp,
     NavigateDown,
-    ToggleSleep,
+    /// Turning the knob while it is held down.
+    PressedNavigateUp,
+    PressedNavigateDown,
+    /// Short press of the knob.
+    Press,
+    /// Knob held down for a while.
+    LongPress,
+
+    // remote control
+    /// Shows a message until the server sends a new one.
+    ShowMessage(String),
+    ShowView(View),
+    SetSleep(bool),
+    /// Brightness in percent.
+    SetBrightness(u8),
+}
+
+impl Event {
+    /// Whether the event comes from the knob.
+    pub fn is_input(&self) -> bool {
+        matches!(
+            self,
+            Event::NavigateUp
+                | Event::NavigateDown
+                | Event::PressedNavigateUp
+                | Event::PressedNavigateDown
+                | Event::Press
+                | Event::LongPress
+        )
+    }
 }
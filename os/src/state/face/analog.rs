@@ -0,0 +1,91 @@
+use super::Face;
+use crate::{font::FONT_6X9, frame::Frame};
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Timelike};
+use chrono_tz::Tz;
+use embedded_graphics::{
+    mono_font::MonoTextStyle,
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::{Circle, Line, PrimitiveStyle},
+    text::{Baseline, Text},
+};
+
+const CENTER: Point = Point::new(64, 32);
+const RADIUS: i32 = 30;
+
+/// Dial with hour ticks and hour, minute and second hands.
+pub struct Analog;
+
+impl Face for Analog {
+    fn name(&self) -> &'static str {
+        "analog"
+    }
+
+    fn shows_seconds(&self) -> bool {
+        true
+    }
+
+    fn draw(&self, frame: &mut Frame, time: &DateTime<Tz>) -> Result<()> {
+        // dial with longer ticks every quarter
+
+        Circle::with_center(CENTER, RADIUS as u32 * 2 + 1)
+            .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+            .draw(frame)
+            .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+        for hour in 0..12u32 {
+            let length = if hour.is_multiple_of(3) { 6 } else { 3 };
+            let angle = hour as f32 * 30.0;
+            Line::new(
+                point_at(angle, RADIUS - 1 - length),
+                point_at(angle, RADIUS - 1),
+            )
+            .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+            .draw(frame)
+            .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+        }
+
+        // date next to the dial
+
+        let style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+        let weekday = time.weekday().to_string().to_uppercase();
+        for (text, y) in [(weekday, 2), (format!("{:02}", time.day()), 12)] {
+            Text::with_baseline(&text, Point::new(2, y), style, Baseline::Top)
+                .draw(frame)
+                .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+        }
+
+        // hands
+
+        let seconds = time.second() as f32;
+        let minutes = time.minute() as f32 + seconds / 60.0;
+        let hours = (time.hour() % 12) as f32 + minutes / 60.0;
+        let hands = [
+            (hours * 30.0, 15, 3),
+            (minutes * 6.0, 24, 2),
+            (seconds * 6.0, 27, 1),
+        ];
+        for (angle, length, width) in hands {
+            Line::new(CENTER, point_at(angle, length))
+                .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, width))
+                .draw(frame)
+                .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+        }
+        Circle::with_center(CENTER, 5)
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+            .draw(frame)
+            .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+
+        Ok(())
+    }
+}
+
+/// Point `distance` away from the center, at `degrees` clockwise from 12 o'clock.
+fn point_at(degrees: f32, distance: i32) -> Point {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    CENTER
+        + Point::new(
+            (sin * distance as f32).round() as i32,
+            -(cos * distance as f32).round() as i32,
+        )
+}
@@ -0,0 +1,92 @@
+use super::Face;
+use crate::frame::Frame;
+use anyhow::Result;
+use chrono::{DateTime, Timelike};
+use chrono_tz::Tz;
+use embedded_graphics::{
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::{PrimitiveStyle, Rectangle},
+};
+
+const WIDTH: i32 = 24;
+const HEIGHT: i32 = 52;
+const THICKNESS: i32 = 5;
+const TOP: i32 = (64 - HEIGHT) / 2;
+
+/// Segments a to g of each digit, as in a seven segment display.
+const DIGITS: [[bool; 7]; 10] = [
+    [true, true, true, true, true, true, false],
+    [false, true, true, false, false, false, false],
+    [true, true, false, true, true, false, true],
+    [true, true, true, true, false, false, true],
+    [false, true, true, false, false, true, true],
+    [true, false, true, true, false, true, true],
+    [true, false, true, true, true, true, true],
+    [true, true, true, false, false, false, false],
+    [true, true, true, true, true, true, true],
+    [true, true, true, true, false, true, true],
+];
+
+/// Hours and minutes in seven segment digits filling the whole screen, with a blinking colon.
+pub struct BigDigits;
+
+impl Face for BigDigits {
+    fn name(&self) -> &'static str {
+        "big"
+    }
+
+    fn shows_seconds(&self) -> bool {
+        true
+    }
+
+    fn draw(&self, frame: &mut Frame, time: &DateTime<Tz>) -> Result<()> {
+        let digits = [
+            time.hour() / 10,
+            time.hour() % 10,
+            time.minute() / 10,
+            time.minute() % 10,
+        ];
+        for (digit, x) in digits.into_iter().zip([4, 32, 72, 100]) {
+            draw_digit(frame, digit as usize, Point::new(x, TOP))?;
+        }
+
+        if time.second().is_multiple_of(2) {
+            for y in [TOP + 15, TOP + HEIGHT - 20] {
+                Rectangle::new(Point::new(61, y), Size::new(5, 5))
+                    .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                    .draw(frame)
+                    .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn draw_digit(frame: &mut Frame, digit: usize, at: Point) -> Result<()> {
+    let middle = (HEIGHT - THICKNESS) / 2;
+    let horizontal = Size::new((WIDTH - 2 * THICKNESS) as u32, THICKNESS as u32);
+    let upper = Size::new(THICKNESS as u32, (middle - THICKNESS) as u32);
+    let lower = Size::new(THICKNESS as u32, (HEIGHT - 2 * THICKNESS - middle) as u32);
+    let segments = [
+        (Point::new(THICKNESS, 0), horizontal),
+        (Point::new(WIDTH - THICKNESS, THICKNESS), upper),
+        (Point::new(WIDTH - THICKNESS, middle + THICKNESS), lower),
+        (Point::new(THICKNESS, HEIGHT - THICKNESS), horizontal),
+        (Point::new(0, middle + THICKNESS), lower),
+        (Point::new(0, THICKNESS), upper),
+        (Point::new(THICKNESS, middle), horizontal),
+    ];
+
+    for ((offset, size), on) in segments.into_iter().zip(DIGITS[digit]) {
+        if on {
+            Rectangle::new(at + offset, size)
+                .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                .draw(frame)
+                .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+        }
+    }
+
+    Ok(())
+}
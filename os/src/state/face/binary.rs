@@ -0,0 +1,75 @@
+use super::Face;
+use crate::{font::FONT_4X6, frame::Frame};
+use anyhow::Result;
+use chrono::{DateTime, Timelike};
+use chrono_tz::Tz;
+use embedded_graphics::{
+    mono_font::MonoTextStyle,
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::{Circle, PrimitiveStyle},
+    text::{Baseline, Text},
+};
+
+/// Left edges of the six digit columns, in pairs for hours, minutes and seconds.
+const COLUMNS: [i32; 6] = [12, 24, 53, 65, 94, 106];
+
+/// Hours, minutes and seconds as binary coded decimals, one column of dots per digit.
+pub struct Binary;
+
+impl Face for Binary {
+    fn name(&self) -> &'static str {
+        "binary"
+    }
+
+    fn shows_seconds(&self) -> bool {
+        true
+    }
+
+    fn draw(&self, frame: &mut Frame, time: &DateTime<Tz>) -> Result<()> {
+        let digits = [
+            time.hour() / 10,
+            time.hour() % 10,
+            time.minute() / 10,
+            time.minute() % 10,
+            time.second() / 10,
+            time.second() % 10,
+        ];
+        // highest value each column can show, to leave out dots that are never lit
+        let max = [2, 9, 5, 9, 5, 9];
+
+        for ((digit, max), x) in digits.into_iter().zip(max).zip(COLUMNS) {
+            for (row, bit) in [8, 4, 2, 1].into_iter().enumerate() {
+                if bit > max {
+                    continue;
+                }
+
+                let style = if digit & bit != 0 {
+                    PrimitiveStyle::with_fill(BinaryColor::On)
+                } else {
+                    PrimitiveStyle::with_stroke(BinaryColor::On, 1)
+                };
+                Circle::new(Point::new(x, 6 + row as i32 * 12), 9)
+                    .into_styled(style)
+                    .draw(frame)
+                    .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+            }
+        }
+
+        for (label, x) in ["H", "M", "S"]
+            .into_iter()
+            .zip([COLUMNS[0], COLUMNS[2], COLUMNS[4]])
+        {
+            Text::with_baseline(
+                label,
+                Point::new(x + 9, 56),
+                MonoTextStyle::new(&FONT_4X6, BinaryColor::On),
+                Baseline::Top,
+            )
+            .draw(frame)
+            .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+use super::Face;
+use crate::{
+    font::{FONT_6X9, FONT_SEG7_14X24},
+    frame::Frame,
+    state::draw_progress,
+};
+use anyhow::Result;
+use chrono::{DateTime, Timelike};
+use chrono_tz::Tz;
+use embedded_graphics::{
+    mono_font::MonoTextStyle,
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::{PrimitiveStyle, Rectangle},
+    text::{Baseline, Text},
+};
+
+/// Date, seven segment time and a second indicator in a thick frame.
+pub struct Digital;
+
+impl Face for Digital {
+    fn name(&self) -> &'static str {
+        "digital"
+    }
+
+    fn shows_seconds(&self) -> bool {
+        true
+    }
+
+    fn draw(&self, frame: &mut Frame, time: &DateTime<Tz>) -> Result<()> {
+        // frame
+
+        Rectangle::new(Point::new(0, 0), Size::new(128, 64))
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+            .draw(frame)
+            .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+        Rectangle::new(Point::new(4, 4), Size::new(120, 56))
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::Off))
+            .draw(frame)
+            .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+
+        // text
+
+        let date = format!("{}", time.date_naive().format("%Y-%m-%d"));
+        Text::new(
+            &date,
+            Point::new(32, 16),
+            MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+        )
+        .draw(frame)
+        .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+
+        let clock = format!("{}", time.time().format("%H:%M"));
+        Text::with_baseline(
+            &clock,
+            Point::new(25, 20),
+            MonoTextStyle::new(&FONT_SEG7_14X24, BinaryColor::On),
+            Baseline::Top,
+        )
+        .draw(frame)
+        .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+
+        // second indicator
+
+        draw_progress(frame, 48, (time.second() + 1) as f32 / 60.0)
+    }
+}
@@ -0,0 +1,32 @@
+use crate::frame::Frame;
+use anyhow::Result;
+use chrono::DateTime;
+use chrono_tz::Tz;
+
+mod analog;
+mod big_digits;
+mod binary;
+mod digital;
+mod words;
+
+/// A way of showing the time in the Clock view.
+pub trait Face {
+    /// Name to pick the face with the `CLOCK_FACE` env var.
+    fn name(&self) -> &'static str;
+
+    /// Whether the face changes every second, otherwise it is redrawn every minute.
+    fn shows_seconds(&self) -> bool;
+
+    fn draw(&self, frame: &mut Frame, time: &DateTime<Tz>) -> Result<()>;
+}
+
+/// All faces in the order the dial cycles through them.
+pub fn all() -> Vec<Box<dyn Face>> {
+    vec![
+        Box::new(digital::Digital),
+        Box::new(analog::Analog),
+        Box::new(big_digits::BigDigits),
+        Box::new(binary::Binary),
+        Box::new(words::Words),
+    ]
+}
@@ -0,0 +1,79 @@
+use super::Face;
+use crate::{
+    font::{FONT_6X9, FONT_9X15_BOLD},
+    frame::Frame,
+    layout::{self, Line},
+};
+use anyhow::Result;
+use chrono::{DateTime, Timelike};
+use chrono_tz::Tz;
+use embedded_graphics::{
+    mono_font::MonoTextStyle,
+    pixelcolor::BinaryColor,
+    prelude::*,
+    text::{Baseline, Text},
+};
+
+const HOURS: [&str; 12] = [
+    "TWELVE", "ONE", "TWO", "THREE", "FOUR", "FIVE", "SIX", "SEVEN", "EIGHT", "NINE", "TEN",
+    "ELEVEN",
+];
+
+/// The time spelled out to the nearest five minutes.
+pub struct Words;
+
+impl Face for Words {
+    fn name(&self) -> &'static str {
+        "words"
+    }
+
+    fn shows_seconds(&self) -> bool {
+        false
+    }
+
+    fn draw(&self, frame: &mut Frame, time: &DateTime<Tz>) -> Result<()> {
+        let text = spell(time.hour(), time.minute());
+        let area = Size::new(120, 60);
+        let layout = layout::layout(&text, &[&FONT_9X15_BOLD, &FONT_6X9], area);
+        let lines = &layout.pages[0];
+
+        // centered both ways
+
+        let line_height = layout.font.character_size.height as i32;
+        let top = (64 - lines.len() as i32 * line_height) / 2;
+        for (n, line) in lines.iter().enumerate() {
+            let (Line::Text(text) | Line::Ticker(text)) = line;
+            let width = (text.chars().count() * layout.font.character_size.width as usize) as i32;
+            Text::with_baseline(
+                text,
+                Point::new((128 - width) / 2, top + n as i32 * line_height),
+                MonoTextStyle::new(layout.font, BinaryColor::On),
+                Baseline::Top,
+            )
+            .draw(frame)
+            .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Spells out the time, e.g. `IT IS TWENTY PAST TEN`.
+fn spell(hour: u32, minute: u32) -> String {
+    let five = (minute + 2) / 5;
+    let hour = if five > 6 { hour + 1 } else { hour };
+    let name = HOURS[hour as usize % 12];
+
+    let minutes = match five {
+        1 | 11 => "FIVE",
+        2 | 10 => "TEN",
+        3 | 9 => "QUARTER",
+        4 | 8 => "TWENTY",
+        5 | 7 => "TWENTY-FIVE",
+        6 => "HALF",
+        _ => return format!("IT IS {name} O'CLOCK"),
+    };
+    let relation = if five > 6 { "TO" } else { "PAST" };
+
+    format!("IT IS {minutes} {relation} {name}")
+}
@@ -0,0 +1,94 @@
+use crate::{message, storage};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+
+const STORAGE_KEY: &str = "inbox";
+
+/// Last received message and whether it has been shown yet, persisted across restarts.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Inbox {
+    pub message: Option<String>,
+    version: Option<String>,
+    pub unread: bool,
+    receipt_pending: bool,
+    /// Last message from the server, which a message set by remote control hides until it
+    /// changes.
+    #[serde(default)]
+    remote: Option<String>,
+    #[serde(default)]
+    local: bool,
+}
+
+impl Inbox {
+    pub fn load() -> Self {
+        storage::load(STORAGE_KEY).unwrap_or_default()
+    }
+
+    pub fn receive(&mut self, msg: message::Message) {
+        let remote = self.remote.as_deref().or(self.message.as_deref());
+        if remote == Some(msg.text.as_str()) && self.version == msg.version {
+            return;
+        }
+
+        // a resent text is a new version the server waits for a receipt of, only servers
+        // without versions are compared by text
+
+        let new = match msg.version {
+            Some(_) => self.version != msg.version,
+            None => self.message.as_deref() != Some(msg.text.as_str()),
+        };
+        if new {
+            info!("received new message");
+            self.unread = true;
+            self.receipt_pending = false;
+        }
+        self.remote = Some(msg.text.clone());
+        self.message = Some(msg.text);
+        self.version = msg.version;
+        self.local = false;
+        self.save();
+    }
+
+    /// Shows a message set by remote control, without a read receipt to the server.
+    pub fn receive_local(&mut self, text: String) {
+        info!("received message by remote control");
+        self.message = Some(text);
+        self.unread = true;
+        self.receipt_pending = false;
+        self.local = true;
+        self.save();
+    }
+
+    pub fn mark_read(&mut self) {
+        if !self.unread {
+            return;
+        }
+
+        info!("mark message as read");
+        self.unread = false;
+        self.receipt_pending = self.version.is_some() && !self.local;
+        self.save();
+        self.send_receipt();
+    }
+
+    /// Sends a pending read receipt, retried on every fetch until the server accepts it.
+    pub fn send_receipt(&mut self) {
+        let Some(version) = self.version.as_deref().filter(|_| self.receipt_pending) else {
+            return;
+        };
+
+        match message::report_seen(version) {
+            Ok(()) => {
+                self.receipt_pending = false;
+                self.save();
+            }
+            Err(err) => error!("failed to send read receipt: {err:?}"),
+        }
+    }
+
+    fn save(&self) {
+        if let Err(err) = storage::save(STORAGE_KEY, self) {
+            error!("failed to save inbox: {err:?}");
+        }
+    }
+}
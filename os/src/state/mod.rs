@@ -1,31 +1,78 @@
 pub mod event;
 
-use crate::{message, weather};
+use crate::{
+    alarm, calendar,
+    control::{self, Control},
+    font::{FONT_4X6, FONT_6X9, FONT_9X15_BOLD, FONT_SEG7_14X24},
+    frame::Frame,
+    icon,
+    layout::{self, Layout, Line as TextLine},
+    message,
+    mqtt::{self, Mqtt},
+    news::{self, Headline},
+    storage,
+    transit::{self, Departure},
+    weather::{self, Condition},
+};
 use anyhow::Result;
 pub use event::Event;
+mod agenda;
+mod alarms;
+mod face;
+mod inbox;
+mod pomodoro;
+mod timers;
+mod transition;
 mod view;
+mod world_clock;
+use agenda::{Agenda, Row};
+use alarms::{Alarms, Field, Status};
 use chrono::{DateTime, NaiveTime, Timelike, Utc};
 use chrono_tz::Tz;
 use embedded_graphics::{
-    mono_font::{
-        MonoTextStyle,
-        ascii::{FONT_6X9, FONT_9X15_BOLD},
-    },
+    mono_font::MonoTextStyle,
     pixelcolor::BinaryColor,
     prelude::*,
     primitives::{Circle, Line, PrimitiveStyle, Rectangle},
-    text::Text,
+    text::{Baseline, Text},
 };
+use inbox::Inbox;
 use log::{error, info};
-use view::View;
+use pomodoro::{Phase, Pomodoro};
+use timers::{Countdown, Stopwatch};
+use transition::{Direction, Transition};
+pub use view::View;
+use world_clock::WorldClock;
+
+const FACE_STORAGE_KEY: &str = "clock_face";
+const BRIGHTNESS_STORAGE_KEY: &str = "brightness";
+
+/// Area between the top and bottom bar of the Mailbox view.
+const MAILBOX_TEXT_AREA: Rectangle = Rectangle::new(Point::new(4, 9), Size::new(120, 46));
 
 pub struct State {
     view: View,
+    /// Last rendered frame, before it is dimmed for the display.
+    frame: Frame,
+    /// Brightness the shown frame was dimmed to.
+    dimmed: u8,
+    dirty: bool,
+    redraw_at: Option<DateTime<Utc>>,
+    transition: Option<Transition>,
+    transition_config: transition::Config,
     last_fetch: Option<DateTime<Utc>>,
+    fetched: control::Fetched,
     sleep: bool,
+    /// Brightness in percent, the contrast of the OLED or dithering in the simulator.
+    brightness: u8,
+    mqtt: Option<Mqtt>,
+    control: Option<Control>,
 
     time: NaiveTime,
     timezone: Tz,
+    faces: Vec<Box<dyn face::Face>>,
+    face: usize,
+    world_clock: WorldClock,
 
     temperature: f32,
     relative_humidity_percent: f32,
@@ -33,9 +80,25 @@ pub struct State {
     wind_speed_km_h: f32,
     wind_direction_deg: i32,
     rain_in_x_hours: Option<usize>,
+    condition: Option<Condition>,
+    weather_failed: bool,
 
-    message: Option<String>,
+    departures: Vec<Departure>,
+    departures_failed: bool,
+
+    inbox: Inbox,
+    push: Option<message::Subscription>,
+    page: usize,
     current_letter: (usize, DateTime<Utc>),
+
+    headlines: Vec<Headline>,
+
+    agenda: Agenda,
+
+    alarms: Alarms,
+    countdown: Countdown,
+    stopwatch: Stopwatch,
+    pomodoro: Pomodoro,
 }
 
 impl State {
@@ -45,15 +108,40 @@ impl State {
             .parse::<Tz>()
             .unwrap_or(chrono_tz::UTC);
 
+        let world_clock = WorldClock::load(timezone);
+
         info!("Using timezone: {}", timezone);
 
+        // the face picked on the dial wins over the configured one
+
+        let faces = face::all();
+        let face_name = storage::load::<String>(FACE_STORAGE_KEY)
+            .unwrap_or(std::env::var("CLOCK_FACE").unwrap_or("digital".into()));
+        let face = faces
+            .iter()
+            .position(|face| face.name() == face_name)
+            .unwrap_or(0);
+
         State {
             view: View::Clock,
+            frame: Frame::new(),
+            dimmed: 100,
+            dirty: true,
+            redraw_at: None,
+            transition: None,
+            transition_config: transition::Config::from_env(),
             last_fetch: None,
+            fetched: control::Fetched::default(),
             sleep: false,
+            brightness: storage::load(BRIGHTNESS_STORAGE_KEY).unwrap_or(100),
+            mqtt: mqtt::connect(),
+            control: control::start(),
 
             time: Utc::now().time(),
             timezone,
+            world_clock,
+            faces,
+            face,
 
             temperature: -0.0,
             relative_humidity_percent: 0.0,
@@ -61,9 +149,25 @@ impl State {
             wind_speed_km_h: 0.0,
             wind_direction_deg: 0,
             rain_in_x_hours: None,
+            condition: None,
+            weather_failed: false,
+
+            departures: Vec::new(),
+            departures_failed: false,
 
-            message: None,
+            inbox: Inbox::load(),
+            push: message::subscribe(),
+            page: 0,
             current_letter: (0, Utc::now()),
+
+            headlines: Vec::new(),
+
+            agenda: Agenda::load(),
+
+            alarms: Alarms::load(),
+            countdown: Countdown::new(),
+            stopwatch: Stopwatch::new(),
+            pomodoro: Pomodoro::load(),
         }
     }
 
@@ -92,18 +196,75 @@ impl State {
                     self.wind_speed_km_h = w.wind_speed_km_h;
                     self.wind_direction_deg = w.wind_direction_deg as i32;
                     self.rain_in_x_hours = w.rain_in_x_hours;
+                    self.condition = Some(w.condition);
+                    self.weather_failed = false;
+                    self.fetched.weather = Some(Utc::now());
                 }
                 Err(err) => {
                     error!("failed to fetch weather data: {err:?}");
+                    self.weather_failed = true;
+                }
+            }
+
+            // departures
+
+            if transit::is_configured() {
+                match transit::fetch() {
+                    Ok(departures) => {
+                        self.departures = departures;
+                        self.departures_failed = false;
+                        self.fetched.departures = Some(Utc::now());
+                    }
+                    Err(err) => {
+                        error!("failed to fetch departures: {err:?}");
+                        self.departures_failed = true;
+                    }
                 }
             }
 
             // message
 
             match message::fetch() {
-                Ok(msg) => self.message = Some(msg),
+                Ok(msg) => {
+                    self.inbox.receive(msg);
+                    self.fetched.message = Some(Utc::now());
+                }
                 Err(err) => error!("failed to fetch message data: {err:?}"),
             }
+            self.inbox.send_receipt();
+
+            // news
+
+            if news::is_configured() {
+                match news::fetch() {
+                    Ok(headlines) => {
+                        self.headlines = headlines;
+                        self.fetched.news = Some(Utc::now());
+                    }
+                    Err(err) => error!("failed to fetch news: {err:?}"),
+                }
+            }
+
+            // calendar
+
+            if calendar::is_configured() {
+                match self.agenda.fetch(self.timezone) {
+                    Ok(()) => self.fetched.calendar = self.agenda.fetched_at,
+                    Err(err) => error!("failed to fetch calendar: {err:?}"),
+                }
+            }
+
+            // alarms
+
+            match alarm::fetch() {
+                Ok(alarms) => {
+                    self.alarms.set_remote(alarms);
+                    self.fetched.alarms = Some(Utc::now());
+                }
+                Err(err) => error!("failed to fetch alarms: {err:?}"),
+            }
+            self.pomodoro.send_reports();
+            self.dirty = true;
         }
     }
 
@@ -112,36 +273,408 @@ impl State {
         D: DrawTarget<Color = BinaryColor>,
         D::Error: Send + Sync + core::fmt::Debug + 'static,
     {
+        // knob input is published for automations
+
+        if let Some(mqtt) = &self.mqtt {
+            match event {
+                Event::Press => mqtt.publish_input("press"),
+                Event::LongPress => mqtt.publish_input("long_press"),
+                Event::NavigateUp | Event::PressedNavigateUp => mqtt.publish_input("up"),
+                Event::NavigateDown | Event::PressedNavigateDown => mqtt.publish_input("down"),
+                _ => {}
+            }
+        }
+
+        // the first input on a dimmed screen only lights it up again, unless something rings
+
+        if event.is_input() {
+            let ringing = self.alarms.is_ringing() || self.countdown.is_ringing();
+            let dimmed = self.pomodoro.is_dimmed(Utc::now()) && !ringing;
+            self.pomodoro.touch(Utc::now());
+            if dimmed {
+                self.dirty = true;
+                return;
+            }
+        }
+
         match event {
             Event::Tick => {
-                std::thread::sleep(std::time::Duration::from_millis(10));
                 self.time = Utc::now().time();
                 self.fetch();
 
-                if let Err(err) = self.render(display) {
+                // messages pushed by the server show up right away
+
+                if let Some(push) = &self.push {
+                    for msg in push.messages() {
+                        self.inbox.receive(msg);
+                        self.fetched.message = Some(Utc::now());
+                        self.dirty = true;
+                    }
+                }
+
+                // commands received by remote control
+
+                let mut events = self.mqtt.as_mut().map(Mqtt::events).unwrap_or_default();
+                events.extend(
+                    self.control
+                        .as_ref()
+                        .map(Control::events)
+                        .unwrap_or_default(),
+                );
+                for event in events {
+                    self.update(display, event);
+                }
+                let status = self.status();
+                if let Some(mqtt) = &mut self.mqtt {
+                    mqtt.publish_status(status);
+                }
+                if let Some(control) = &self.control {
+                    control.set_status(self.control_status());
+                }
+
+                // wake up the display for a due alarm or an expired countdown
+
+                let alarm = self.alarms.check(Utc::now(), self.timezone);
+                let countdown = self.countdown.check(Utc::now());
+                if alarm || countdown {
+                    self.sleep = false;
+                    self.dirty = true;
+                }
+                if self.pomodoro.check(Utc::now()) {
+                    self.dirty = true;
+                }
+
+                if matches!(self.view, View::Mailbox) && !self.sleep && self.inbox.unread {
+                    self.inbox.mark_read();
+                    self.dirty = true;
+                }
+
+                // only render when something changed or the view asked for it
+
+                let due = self.dirty
+                    || self.transition.is_some()
+                    || self.redraw_at.is_some_and(|at| Utc::now() >= at);
+                if !due {
+                    return;
+                }
+                self.dirty = false;
+                self.redraw_at = None;
+
+                // render offscreen, blended with the previous view while a transition runs
+
+                let mut frame = Frame::new();
+                if let Err(err) = self.render(&mut frame) {
                     error!("renderer failed: {err:?}");
                 }
+                if let Some(transition) = &mut self.transition {
+                    frame = transition.step(&frame);
+                    if transition.is_done() {
+                        self.transition = None;
+                    }
+                }
+
+                // the OLED dims by its contrast, the simulator only by switching pixels off,
+                // which keeps the undimmed frame for the next transition
+
+                let dimmed = if cfg!(feature = "oled") {
+                    100
+                } else {
+                    self.brightness
+                };
+                if frame != self.frame || dimmed != self.dimmed {
+                    let mut shown = frame.clone();
+                    shown.dim(dimmed);
+                    if let Err(err) = shown.draw(display) {
+                        error!("drawing frame failed: {err:?}");
+                    }
+                    if let Some(control) = &self.control {
+                        control.set_frame(&frame);
+                    }
+                    self.frame = frame;
+                    self.dimmed = dimmed;
+                }
+            }
+            // turning with the knob pressed still edits, so the view can't change under the
+            // editor
+            Event::NavigateDown | Event::PressedNavigateDown if self.alarms.editor.is_some() => {
+                self.alarms.turn(1);
+                self.dirty = true;
+            }
+            Event::NavigateUp | Event::PressedNavigateUp if self.alarms.editor.is_some() => {
+                self.alarms.turn(-1);
+                self.dirty = true;
+            }
+            Event::NavigateDown | Event::PressedNavigateDown if self.countdown.setting => {
+                self.countdown.turn(1);
+                self.dirty = true;
+            }
+            Event::NavigateUp | Event::PressedNavigateUp if self.countdown.setting => {
+                self.countdown.turn(-1);
+                self.dirty = true;
+            }
+            Event::PressedNavigateDown | Event::PressedNavigateUp
+                if matches!(self.view, View::Clock) =>
+            {
+                let step = if matches!(event, Event::PressedNavigateDown) {
+                    1
+                } else {
+                    self.faces.len() - 1
+                };
+                self.face = (self.face + step) % self.faces.len();
+                info!("switch to clock face {}", self.faces[self.face].name());
+                if let Err(err) = storage::save(FACE_STORAGE_KEY, &self.faces[self.face].name()) {
+                    error!("failed to save clock face: {err:?}");
+                }
+                self.dirty = true;
             }
-            Event::NavigateDown => {
-                self.view = self.view.next();
+            Event::NavigateDown | Event::PressedNavigateDown => {
+                // page through a long message, the headlines or the agenda before moving on
+                // to the next view
+
+                if self.page + 1 < self.pages() {
+                    self.page += 1;
+                } else {
+                    self.view = self.view.next();
+                    self.page = 0;
+                    self.start_transition(Direction::Left);
+                }
                 self.current_letter = (0, Utc::now());
+                self.dirty = true;
             }
-            Event::NavigateUp => {
-                self.view = self.view.previous();
+            Event::NavigateUp | Event::PressedNavigateUp => {
+                if self.page > 0 {
+                    self.page -= 1;
+                } else {
+                    self.view = self.view.previous();
+                    self.page = 0;
+                    self.start_transition(Direction::Right);
+                }
                 self.current_letter = (0, Utc::now());
+                self.dirty = true;
+            }
+            Event::Press => {
+                if self.alarms.is_ringing() {
+                    self.alarms.snooze();
+                } else if self.countdown.is_ringing() {
+                    self.countdown.dismiss();
+                } else if self.alarms.editor.is_some() {
+                    self.alarms.next_field();
+                } else if matches!(self.view, View::WorldClock) && !self.sleep {
+                    self.world_clock.cycle_home();
+                } else if matches!(self.view, View::Timer) && !self.sleep {
+                    self.countdown.press(Utc::now());
+                } else if matches!(self.view, View::Stopwatch) && !self.sleep {
+                    self.stopwatch.toggle(Utc::now());
+                } else if matches!(self.view, View::Pomodoro) && !self.sleep {
+                    self.pomodoro.press(Utc::now());
+                } else {
+                    info!("toggle sleep mode");
+                    self.sleep = !self.sleep;
+                }
+                self.dirty = true;
+            }
+            Event::LongPress => {
+                if self.alarms.is_ringing() || self.alarms.is_snoozed() {
+                    self.alarms.dismiss();
+                } else if self.countdown.is_ringing() {
+                    self.countdown.dismiss();
+                } else if self.alarms.editor.is_some() {
+                    self.alarms.finish_editing();
+                } else {
+                    match self.view {
+                        View::Alarm => self.alarms.start_editing(),
+                        View::Timer => self.countdown.reset(),
+                        View::Stopwatch => self.stopwatch.reset(),
+                        View::Pomodoro => self.pomodoro.reset(Utc::now()),
+                        _ => {}
+                    }
+                }
+                self.dirty = true;
+            }
+            Event::ShowMessage(text) => {
+                self.inbox.receive_local(text);
+                self.dirty = true;
             }
-            Event::ToggleSleep => {
-                info!("toggle sleep mode");
-                self.sleep = !self.sleep;
+            Event::ShowView(view) => {
+                if view != self.view {
+                    info!("show {} view", view.name());
+                    self.view = view;
+                    self.page = 0;
+                    self.current_letter = (0, Utc::now());
+                    self.start_transition(Direction::Left);
+                    self.dirty = true;
+                }
+            }
+            Event::SetSleep(sleep) => {
+                info!("set sleep mode to {sleep}");
+                self.sleep = sleep;
+                self.dirty = true;
+            }
+            Event::SetBrightness(percent) => {
+                info!("set brightness to {percent} %");
+                self.brightness = percent.min(100);
+                if let Err(err) = storage::save(BRIGHTNESS_STORAGE_KEY, &self.brightness) {
+                    error!("failed to save brightness: {err:?}");
+                }
+                self.dirty = true;
             }
         }
     }
 
-    fn render<D>(&mut self, display: &mut D) -> Result<()>
-    where
-        D: DrawTarget<Color = BinaryColor>,
-        D::Error: Send + Sync + core::fmt::Debug + 'static,
-    {
+    /// What the cube shows, for remote control.
+    fn status(&self) -> mqtt::Status {
+        mqtt::Status {
+            view: self.view.name(),
+            sleep: self.sleep,
+            brightness: self.brightness,
+            unread: self.inbox.unread,
+        }
+    }
+
+    /// Status served by the control api.
+    fn control_status(&self) -> control::Status {
+        control::Status {
+            view: self.view.name(),
+            page: self.page,
+            sleep: self.sleep,
+            brightness: self.brightness,
+            unread: self.inbox.unread,
+            fetched: self.fetched.clone(),
+            weather: self.condition.map(|condition| control::Weather {
+                temperature: self.temperature,
+                relative_humidity_percent: self.relative_humidity_percent,
+                surface_pressure_hpa: self.surface_pressure_hpa,
+                wind_speed_km_h: self.wind_speed_km_h,
+                wind_direction_deg: self.wind_direction_deg,
+                rain_in_x_hours: self.rain_in_x_hours,
+                condition,
+                failed: self.weather_failed,
+            }),
+        }
+    }
+
+    /// Brightness in percent, which the OLED sets as its contrast.
+    #[cfg(feature = "oled")]
+    pub fn brightness(&self) -> u8 {
+        self.brightness
+    }
+
+    /// Whether the buzzer should sound, it beeps in sync with the flashing alarm screen.
+    #[cfg(feature = "oled")]
+    pub fn buzzing(&self) -> bool {
+        let now = Utc::now();
+        (self.alarms.is_ringing() || self.countdown.is_ringing() || self.pomodoro.is_alerting(now))
+            && now.timestamp_subsec_millis() < 500
+    }
+
+    fn start_transition(&mut self, direction: Direction) {
+        if self.sleep {
+            return;
+        }
+
+        self.transition = self.transition_config.start(self.frame.clone(), direction);
+    }
+
+    fn mailbox_layout(&self) -> Layout {
+        layout::layout(
+            self.inbox
+                .message
+                .as_deref()
+                .unwrap_or("No message available."),
+            &[&FONT_9X15_BOLD, &FONT_6X9],
+            MAILBOX_TEXT_AREA.size,
+        )
+    }
+
+    /// Number of pages the current view is turned through with the dial.
+    fn pages(&self) -> usize {
+        match self.view {
+            View::Mailbox => self.mailbox_layout().pages.len(),
+            View::News => self.headlines.len(),
+            View::Agenda => self.agenda.pages(Utc::now(), self.timezone),
+            _ => 1,
+        }
+    }
+
+    /// Scrolls the ticker one char further, pausing at its start and end.
+    fn advance_ticker(&mut self, max_offset: usize) {
+        let ms_since = Utc::now()
+            .signed_duration_since(self.current_letter.1)
+            .num_milliseconds();
+        let pause = self.current_letter.0 == 0 || self.current_letter.0 >= max_offset;
+
+        if ms_since > if pause { 1500 } else { 200 } {
+            let idx = self.current_letter.0 + 1;
+            self.current_letter = (if idx > max_offset { 0 } else { idx }, Utc::now());
+        }
+
+        let pause = self.current_letter.0 == 0 || self.current_letter.0 >= max_offset;
+        let delay = chrono::Duration::milliseconds(if pause { 1500 } else { 200 } + 1);
+        self.request_redraw(self.current_letter.1 + delay);
+    }
+
+    /// Asks for the next render at `at` at the latest, for views that change over time.
+    fn request_redraw(&mut self, at: DateTime<Utc>) {
+        self.redraw_at = Some(self.redraw_at.map_or(at, |current| current.min(at)));
+    }
+
+    fn render(&mut self, display: &mut Frame) -> Result<()> {
+        // a ringing alarm or countdown takes over the screen and flashes every half second
+
+        if self.alarms.is_ringing() || self.countdown.is_ringing() {
+            let now = Utc::now();
+            let millis = now.timestamp_subsec_millis();
+            self.request_redraw(now + chrono::Duration::milliseconds(500 - millis as i64 % 500));
+
+            let (background, foreground) = if millis < 500 {
+                (BinaryColor::On, BinaryColor::Off)
+            } else {
+                (BinaryColor::Off, BinaryColor::On)
+            };
+            display
+                .clear(background)
+                .map_err(|e| anyhow::anyhow!("clear failed: {:?}", e))?;
+
+            let (title, time, hint) = if self.alarms.is_ringing() {
+                (
+                    "ALARM",
+                    format!("{}", now.with_timezone(&self.timezone).format("%H:%M")),
+                    "PRESS: SNOOZE  HOLD: STOP",
+                )
+            } else {
+                ("TIMER", "00:00".to_string(), "PRESS: STOP")
+            };
+
+            Text::with_baseline(
+                title,
+                Point::new(49, 2),
+                MonoTextStyle::new(&FONT_6X9, foreground),
+                Baseline::Top,
+            )
+            .draw(display)
+            .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+
+            Text::with_baseline(
+                &time,
+                Point::new(29, 14),
+                MonoTextStyle::new(&FONT_SEG7_14X24, foreground),
+                Baseline::Top,
+            )
+            .draw(display)
+            .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+
+            Text::with_baseline(
+                hint,
+                Point::new((128 - hint.len() as i32 * 4) / 2, 50),
+                MonoTextStyle::new(&FONT_4X6, foreground),
+                Baseline::Top,
+            )
+            .draw(display)
+            .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+
+            return Ok(());
+        }
+
         if self.sleep {
             display
                 .clear(BinaryColor::Off)
@@ -149,55 +682,113 @@ impl State {
             return Ok(());
         }
 
-        match &self.view {
-            View::Clock => {
-                // frame
-
-                Rectangle::new(Point::new(0, 0), Size::new(128, 64))
-                    .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
-                    .draw(display)
-                    .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
-                Rectangle::new(Point::new(4, 4), Size::new(120, 56))
-                    .into_styled(PrimitiveStyle::with_fill(BinaryColor::Off))
-                    .draw(display)
-                    .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+        // dimmed during focus blocks, only a thin line shows the progress
 
-                // text
+        let now = Utc::now();
+        if let Some(at) = self.pomodoro.dims_at().filter(|at| *at > now) {
+            self.request_redraw(at);
+        }
+        if self.pomodoro.is_dimmed(now) {
+            display
+                .clear(BinaryColor::Off)
+                .map_err(|e| anyhow::anyhow!("clear failed: {:?}", e))?;
 
-                let local_time = Utc::now().with_timezone(&self.timezone);
-                let date = format!("{}", local_time.date_naive().format("%Y-%m-%d"));
-                Text::new(
-                    &date,
-                    Point::new(32, 16),
-                    MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
-                )
+            let width = (128.0 * self.pomodoro.progress(now)) as u32;
+            Rectangle::new(Point::new(0, 63), Size::new(width, 1))
+                .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
                 .draw(display)
                 .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+            self.request_redraw(now + chrono::Duration::seconds(1));
+            return Ok(());
+        }
 
-                let time = format!("{}", local_time.time().format("%H:%M"));
-                Text::new(
-                    &time,
-                    Point::new(40, 35),
-                    MonoTextStyle::new(&FONT_9X15_BOLD, BinaryColor::On),
-                )
-                .draw(display)
-                .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+        match &self.view {
+            View::Clock => {
+                let face = &self.faces[self.face];
+                let next = if face.shows_seconds() {
+                    now.with_nanosecond(0).unwrap_or(now) + chrono::Duration::seconds(1)
+                } else {
+                    now.with_second(0)
+                        .and_then(|t| t.with_nanosecond(0))
+                        .unwrap_or(now)
+                        + chrono::Duration::minutes(1)
+                };
+                face.draw(display, &now.with_timezone(&self.timezone))?;
+                self.request_redraw(next);
 
-                // second indicator
+                // countdown to the next meeting today
 
-                let start = Point::new(10, 48);
-                let length = 108;
-                let height = 1;
-                Line::new(start, Point::new(start.x + length, start.y))
-                    .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, height))
+                if let Some(event) = self.agenda.next(now, self.timezone) {
+                    let minutes = ((event.start - now).num_seconds() + 59) / 60;
+                    let countdown = match minutes {
+                        m if m >= 60 => format!("{}H {:02}M", m / 60, m % 60),
+                        m => format!("{m}M"),
+                    };
+                    let text = format!("IN {countdown}: {}", event.summary);
+
+                    Rectangle::new(Point::new(0, 56), Size::new(128, 8))
+                        .into_styled(PrimitiveStyle::with_fill(BinaryColor::Off))
+                        .draw(display)
+                        .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+                    Text::with_baseline(
+                        &text.chars().take(31).collect::<String>(),
+                        Point::new(2, 57),
+                        MonoTextStyle::new(&FONT_4X6, BinaryColor::On),
+                        Baseline::Top,
+                    )
                     .draw(display)
                     .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
-                let seconds = (local_time.second() + 1) as f32 / 60.0;
-                let x = start.x + (length as f32 * seconds) as i32;
-                Circle::with_center(Point::new(x, start.y), height + 4)
-                    .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                }
+            }
+            View::WorldClock => {
+                // frame
+
+                draw_bars(display, "WORLD CLOCK", "PRESS: NEXT HOME")?;
+                self.request_redraw(
+                    now.with_second(0)
+                        .and_then(|t| t.with_nanosecond(0))
+                        .unwrap_or(now)
+                        + chrono::Duration::minutes(1),
+                );
+
+                // one row per zone with its day offset to home, home is highlighted
+
+                let home_date = now.with_timezone(&self.world_clock.home()).date_naive();
+                for (n, zone) in self.world_clock.zones.iter().enumerate() {
+                    let local_time = now.with_timezone(zone);
+                    let offset = match (local_time.date_naive() - home_date).num_days() {
+                        0 => String::new(),
+                        days => format!("{days:+}"),
+                    };
+                    let row = format!(
+                        "{:<11}{} {:>2}",
+                        world_clock::city(*zone)
+                            .chars()
+                            .take(10)
+                            .collect::<String>(),
+                        local_time.format("%H:%M"),
+                        offset
+                    );
+
+                    let y = 10 + n as i32 * 11;
+                    let color = if n == self.world_clock.home {
+                        Rectangle::new(Point::new(2, y - 1), Size::new(124, 11))
+                            .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                            .draw(display)
+                            .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+                        BinaryColor::Off
+                    } else {
+                        BinaryColor::On
+                    };
+                    Text::with_baseline(
+                        &row,
+                        Point::new(4, y),
+                        MonoTextStyle::new(&FONT_6X9, color),
+                        Baseline::Top,
+                    )
                     .draw(display)
                     .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+                }
             }
             View::Weather => {
                 // frame
@@ -228,6 +819,12 @@ impl State {
                 .draw(display)
                 .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
 
+                icon::draw(
+                    display,
+                    &icon::WIND,
+                    Point::new(x, row_two_y - 7),
+                    BinaryColor::On,
+                )?;
                 Text::new(
                     &format!(
                         "{:.0}km/h ({})",
@@ -244,7 +841,7 @@ impl State {
                             _ => "N/A",
                         },
                     ),
-                    Point::new(x, row_two_y),
+                    Point::new(x + 12, row_two_y),
                     MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
                 )
                 .draw(display)
@@ -274,6 +871,81 @@ impl State {
                 )
                 .draw(display)
                 .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+
+                // condition, or a wifi icon when the weather could not be fetched
+
+                if self.weather_failed {
+                    icon::draw(display, &icon::WIFI, Point::new(108, 44), BinaryColor::On)?;
+                } else if let Some(condition) = self.condition {
+                    let condition_icon = match condition {
+                        Condition::Sun => &icon::SUN,
+                        Condition::Cloud => &icon::CLOUD,
+                        Condition::Rain => &icon::RAIN,
+                        Condition::Snow => &icon::SNOW,
+                    };
+                    icon::draw(
+                        display,
+                        condition_icon,
+                        Point::new(104, 40),
+                        BinaryColor::On,
+                    )?;
+                }
+            }
+            View::Departures => {
+                // frame
+
+                let hint = if !transit::is_configured() {
+                    "NO STOP SET"
+                } else if self.departures_failed {
+                    "NO CONNECTION"
+                } else {
+                    ""
+                };
+                draw_bars(display, "DEPARTURES", hint)?;
+
+                // line, destination and minutes until departure, redrawn when a minute passes
+
+                let departures = self
+                    .departures
+                    .iter()
+                    .filter(|departure| departure.time >= now)
+                    .take(transit::count());
+
+                let mut rows = Vec::new();
+                let mut changes = Vec::new();
+                for departure in departures {
+                    let until = departure.time - now;
+                    let minutes = match until.num_minutes() {
+                        0 => "now".to_string(),
+                        minutes => minutes.to_string(),
+                    };
+                    rows.push(format!(
+                        "{:<4} {:<10} {:>4}",
+                        departure.line.chars().take(4).collect::<String>(),
+                        departure.destination.chars().take(10).collect::<String>(),
+                        minutes
+                    ));
+                    changes.push(
+                        now + chrono::Duration::milliseconds(until.num_milliseconds() % 60_000 + 1),
+                    );
+                }
+                if let Some(at) = changes.into_iter().min() {
+                    self.request_redraw(at);
+                }
+                if rows.is_empty() {
+                    rows.push("No departures.".to_string());
+                }
+
+                for (n, row) in rows.iter().enumerate() {
+                    Text::with_baseline(
+                        row,
+                        Point::new(4, 10 + n as i32 * 9),
+                        MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+                        Baseline::Top,
+                    )
+                    .draw(display)
+                    .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+                }
             }
             View::Mailbox => {
                 // frame
@@ -286,52 +958,556 @@ impl State {
                     .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
                     .draw(display)
                     .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+                icon::draw(display, &icon::ENVELOPE, Point::new(4, 0), BinaryColor::Off)?;
+                Text::with_baseline(
+                    "MESSAGE",
+                    Point::new(16, 1),
+                    MonoTextStyle::new(&FONT_4X6, BinaryColor::Off),
+                    Baseline::Top,
+                )
+                .draw(display)
+                .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
 
                 // text
 
-                let text = self.message.as_deref().unwrap_or("No message available.");
-                let ms_since = Utc::now()
-                    .signed_duration_since(self.current_letter.1)
-                    .num_milliseconds();
+                let layout = self.mailbox_layout();
+                let page_index = self.page.min(layout.pages.len() - 1);
+                let style = MonoTextStyle::new(layout.font, BinaryColor::On);
+                let line_height = layout.font.character_size.height as i32;
 
-                if (self.current_letter.0 != 0 && ms_since > 200) || ms_since > 1500 {
-                    let idx = (self.current_letter.0 + 1).min(text.len());
-                    if idx >= text.len() {
-                        self.current_letter = (0, Utc::now());
-                    } else {
-                        self.current_letter = (idx, Utc::now());
-                    }
+                let page = &layout.pages[page_index];
+                let overflow = |word: &str| word.chars().count() - layout.columns;
+
+                // all tickers on a page share one scroll position
+
+                let max_overflow = page
+                    .iter()
+                    .filter_map(|line| match line {
+                        TextLine::Ticker(word) => Some(overflow(word)),
+                        TextLine::Text(_) => None,
+                    })
+                    .max();
+                if let Some(max_overflow) = max_overflow {
+                    self.advance_ticker(max_overflow);
                 }
 
-                let start = self.current_letter.0.min(text.len());
-                let end = (start + 20).min(text.len());
-                let text_slice = &text[start..end];
+                for (n, line) in page.iter().enumerate() {
+                    let text = match line {
+                        TextLine::Text(text) => text.clone(),
+                        TextLine::Ticker(word) => layout::window(
+                            word,
+                            self.current_letter.0.min(overflow(word)),
+                            layout.columns,
+                        ),
+                    };
 
-                Text::new(
-                    "Message:",
-                    Point::new(6, 26),
+                    Text::with_baseline(
+                        &text,
+                        MAILBOX_TEXT_AREA.top_left + Point::new(0, n as i32 * line_height),
+                        style,
+                        Baseline::Top,
+                    )
+                    .draw(display)
+                    .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+                }
+
+                draw_pages(display, layout.pages.len(), page_index)?;
+            }
+            View::News => {
+                // frame
+
+                let index = self.page.min(self.headlines.len().saturating_sub(1));
+                let headline = self.headlines.get(index);
+                let title = match self.headlines.len() {
+                    0 => "NEWS".to_string(),
+                    count => format!("NEWS {}/{}", index + 1, count),
+                };
+                let source = headline.map_or(String::new(), |headline| {
+                    headline.source.chars().take(30).collect()
+                });
+                draw_bars(display, &title, &source)?;
+
+                // the headline scrolls through the ticker, the dial skips to the next one
+
+                let text = headline.map_or("No news available.", |headline| &headline.title);
+                let columns = layout::columns(&FONT_9X15_BOLD, MAILBOX_TEXT_AREA.size.width);
+                let overflow = text.chars().count().saturating_sub(columns);
+                let line = layout::window(text, self.current_letter.0.min(overflow), columns);
+
+                Text::with_baseline(
+                    &line,
+                    Point::new(MAILBOX_TEXT_AREA.top_left.x, 25),
                     MonoTextStyle::new(&FONT_9X15_BOLD, BinaryColor::On),
+                    Baseline::Top,
                 )
                 .draw(display)
                 .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
 
-                Text::new(
-                    text_slice,
-                    Point::new(6, 44),
-                    MonoTextStyle::new(&FONT_9X15_BOLD, BinaryColor::On),
+                if overflow > 0 {
+                    self.advance_ticker(overflow);
+                }
+            }
+            View::Agenda => {
+                // frame
+
+                let hint = match self.agenda.fetched_at {
+                    _ if !calendar::is_configured() => "NO CALENDAR SET".to_string(),
+                    Some(at) => format!(
+                        "UPDATED {}",
+                        at.with_timezone(&self.timezone).format("%H:%M")
+                    ),
+                    None => "NOT SYNCED YET".to_string(),
+                };
+                draw_bars(display, "AGENDA", &hint)?;
+                self.request_redraw(
+                    now.with_second(0)
+                        .and_then(|t| t.with_nanosecond(0))
+                        .unwrap_or(now)
+                        + chrono::Duration::minutes(1),
+                );
+
+                // day headers with the events below
+
+                let rows = self.agenda.rows(now, self.timezone);
+                let pages = rows.len().div_ceil(agenda::ROWS_PER_PAGE);
+                let page_index = self.page.min(pages - 1);
+                let page = rows
+                    .iter()
+                    .skip(page_index * agenda::ROWS_PER_PAGE)
+                    .take(agenda::ROWS_PER_PAGE);
+
+                for (n, row) in page.enumerate() {
+                    let y = 10 + n as i32 * 9;
+                    match row {
+                        Row::Day(day) => {
+                            Text::with_baseline(
+                                day,
+                                Point::new(4, y + 1),
+                                MonoTextStyle::new(&FONT_4X6, BinaryColor::On),
+                                Baseline::Top,
+                            )
+                            .draw(display)
+                            .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+                            let x = 4 + day.len() as i32 * 4 + 2;
+                            Line::new(Point::new(x, y + 4), Point::new(123, y + 4))
+                                .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+                                .draw(display)
+                                .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+                        }
+                        Row::Event(text) => {
+                            Text::with_baseline(
+                                &text.chars().take(20).collect::<String>(),
+                                Point::new(4, y),
+                                MonoTextStyle::new(&FONT_6X9, BinaryColor::On),
+                                Baseline::Top,
+                            )
+                            .draw(display)
+                            .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+                        }
+                    }
+                }
+
+                draw_pages(display, pages, page_index)?;
+            }
+            View::Alarm => {
+                // frame
+
+                let editor = self.alarms.editor.as_ref();
+                let title = match editor {
+                    None => "ALARMS".to_string(),
+                    Some(editor) if editor.index < self.alarms.local.len() => {
+                        format!("ALARM {}/{}", editor.index + 1, self.alarms.local.len())
+                    }
+                    Some(_) => "NEW ALARM".to_string(),
+                };
+                let hint = match editor.map(|editor| editor.field) {
+                    None if self.alarms.is_snoozed() => "SNOOZED, HOLD TO STOP",
+                    None => "HOLD TO EDIT",
+                    Some(Field::Select) => "TURN: PICK  PRESS: EDIT",
+                    Some(Field::Status) => "PRESS: SAVE  HOLD: DONE",
+                    Some(_) => "PRESS: NEXT  HOLD: DONE",
+                };
+                draw_bars(display, &title, hint)?;
+
+                let style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+                match editor {
+                    // list of all alarms, the ones set on the server are marked
+                    None => {
+                        let alarms = self
+                            .alarms
+                            .local
+                            .iter()
+                            .map(|alarm| (' ', alarm))
+                            .chain(self.alarms.remote.iter().map(|alarm| ('*', alarm)))
+                            .collect::<Vec<_>>();
+
+                        let mut rows = alarms
+                            .iter()
+                            .map(|(marker, alarm)| {
+                                format!(
+                                    "{}{:02}:{:02} {:<5} {}",
+                                    marker,
+                                    alarm.hour,
+                                    alarm.minute,
+                                    alarm.repeat_label().chars().take(5).collect::<String>(),
+                                    if alarm.enabled { "on" } else { "off" }
+                                )
+                            })
+                            .collect::<Vec<_>>();
+                        if rows.is_empty() {
+                            rows.push("No alarms set.".to_string());
+                        } else if rows.len() > 5 {
+                            let more = rows.len() - 4;
+                            rows.truncate(4);
+                            rows.push(format!("+{more} more"));
+                        }
+
+                        for (n, row) in rows.iter().enumerate() {
+                            Text::with_baseline(
+                                row,
+                                Point::new(4, 10 + n as i32 * 9),
+                                style,
+                                Baseline::Top,
+                            )
+                            .draw(display)
+                            .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+                        }
+                    }
+
+                    // the alarm being edited with its selected field highlighted
+                    Some(editor) => {
+                        let time = format!("{:02}:{:02}", editor.draft.hour, editor.draft.minute);
+                        Text::with_baseline(
+                            &time,
+                            Point::new(29, 11),
+                            MonoTextStyle::new(&FONT_SEG7_14X24, BinaryColor::On),
+                            Baseline::Top,
+                        )
+                        .draw(display)
+                        .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+
+                        match editor.field {
+                            Field::Select => {
+                                for (arrow, x) in [("<", 12), (">", 110)] {
+                                    Text::with_baseline(
+                                        arrow,
+                                        Point::new(x, 19),
+                                        style,
+                                        Baseline::Top,
+                                    )
+                                    .draw(display)
+                                    .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+                                }
+                            }
+                            Field::Hour | Field::Minute => {
+                                let x = if editor.field == Field::Hour { 29 } else { 71 };
+                                Rectangle::new(Point::new(x, 36), Size::new(28, 2))
+                                    .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                                    .draw(display)
+                                    .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+                            }
+                            Field::Repeat | Field::Status => {}
+                        }
+
+                        let status = match editor.status {
+                            Status::On => "on",
+                            Status::Off => "off",
+                            Status::Delete => "delete",
+                        };
+                        let fields = [
+                            (editor.draft.repeat_label(), 4, Field::Repeat),
+                            (
+                                status.to_string(),
+                                124 - status.len() as i32 * 6,
+                                Field::Status,
+                            ),
+                        ];
+                        for (text, x, field) in fields {
+                            let color = if field == editor.field {
+                                Rectangle::new(
+                                    Point::new(x - 1, 42),
+                                    Size::new(text.chars().count() as u32 * 6 + 2, 11),
+                                )
+                                .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                                .draw(display)
+                                .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+                                BinaryColor::Off
+                            } else {
+                                BinaryColor::On
+                            };
+                            Text::with_baseline(
+                                &text,
+                                Point::new(x, 43),
+                                MonoTextStyle::new(&FONT_6X9, color),
+                                Baseline::Top,
+                            )
+                            .draw(display)
+                            .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+                        }
+                    }
+                }
+            }
+            View::Timer => {
+                // frame
+
+                let hint = if self.countdown.setting {
+                    "TURN: MINUTES  PRESS: START"
+                } else if self.countdown.is_running() {
+                    "PRESS: PAUSE  HOLD: RESET"
+                } else if self.countdown.is_active() {
+                    "PRESS: RESUME  HOLD: RESET"
+                } else {
+                    "PRESS: SET"
+                };
+                draw_bars(display, "TIMER", hint)?;
+
+                // remaining time, rounded up so it only shows 00:00 once expired
+
+                let remaining = self.countdown.remaining(now).num_milliseconds();
+                if self.countdown.is_running() {
+                    let until_change = match remaining % 1000 {
+                        0 => 1000,
+                        ms => ms,
+                    };
+                    self.request_redraw(now + chrono::Duration::milliseconds(until_change));
+                }
+
+                let seconds = (remaining + 999) / 1000;
+                let time = format!("{:02}:{:02}", seconds / 60, seconds % 60);
+                Text::with_baseline(
+                    &time,
+                    Point::new(29, 12),
+                    MonoTextStyle::new(&FONT_SEG7_14X24, BinaryColor::On),
+                    Baseline::Top,
                 )
                 .draw(display)
                 .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
 
-                // cutoff
+                if self.countdown.setting {
+                    Rectangle::new(Point::new(29, 37), Size::new(28, 2))
+                        .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                        .draw(display)
+                        .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+                }
+
+                // progress, in the style of the clock's second indicator
 
-                Rectangle::new(Point::new(122, 32), Size::new(128, 15))
-                    .into_styled(PrimitiveStyle::with_fill(BinaryColor::Off))
+                if self.countdown.is_active() {
+                    let total = self.countdown.minutes * 60_000;
+                    draw_progress(display, 46, 1.0 - remaining as f32 / total as f32)?;
+                }
+            }
+            View::Stopwatch => {
+                // frame
+
+                let hint = if self.stopwatch.is_running() {
+                    "PRESS: STOP"
+                } else if self.stopwatch.is_active() {
+                    "PRESS: START  HOLD: RESET"
+                } else {
+                    "PRESS: START"
+                };
+                draw_bars(display, "STOPWATCH", hint)?;
+
+                // elapsed time with tenths, or with hours after the first hour
+
+                let elapsed = self.stopwatch.elapsed(now).num_milliseconds();
+                if self.stopwatch.is_running() {
+                    let until_change = 100 - elapsed % 100;
+                    self.request_redraw(now + chrono::Duration::milliseconds(until_change));
+                }
+
+                let seconds = elapsed / 1000;
+                let (time, tenths) = if seconds < 3600 {
+                    let time = format!("{:02}:{:02}", seconds / 60, seconds % 60);
+                    (time, Some(format!(".{}", elapsed % 1000 / 100)))
+                } else {
+                    let time = format!(
+                        "{}:{:02}:{:02}",
+                        seconds / 3600,
+                        seconds / 60 % 60,
+                        seconds % 60
+                    );
+                    (time, None)
+                };
+
+                // past 99 hours the digits only fit in the smaller font
+                let (font, y) = if layout::width(&FONT_SEG7_14X24, &time) <= 128 {
+                    (&FONT_SEG7_14X24, 16)
+                } else {
+                    (&FONT_9X15_BOLD, 20)
+                };
+                let digits_width = layout::width(font, &time) as i32;
+                let tenths_width = tenths
+                    .as_deref()
+                    .map_or(0, |tenths| layout::width(&FONT_9X15_BOLD, tenths) as i32);
+                let x = (128 - digits_width - tenths_width) / 2;
+                Text::with_baseline(
+                    &time,
+                    Point::new(x, y),
+                    MonoTextStyle::new(font, BinaryColor::On),
+                    Baseline::Top,
+                )
+                .draw(display)
+                .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+                if let Some(tenths) = tenths {
+                    Text::with_baseline(
+                        &tenths,
+                        Point::new(x + digits_width, 25),
+                        MonoTextStyle::new(&FONT_9X15_BOLD, BinaryColor::On),
+                        Baseline::Top,
+                    )
                     .draw(display)
                     .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+                }
+            }
+            View::Pomodoro => {
+                // frame
+
+                let title = match self.pomodoro.phase {
+                    Phase::Focus => format!("FOCUS {}", self.pomodoro.cycles + 1),
+                    Phase::Break => "BREAK".to_string(),
+                    Phase::LongBreak => "LONG BREAK".to_string(),
+                };
+                let hint = if self.pomodoro.is_running() {
+                    "PRESS: PAUSE  HOLD: RESET"
+                } else if self.pomodoro.is_active() {
+                    "PRESS: RESUME  HOLD: RESET"
+                } else {
+                    "PRESS: START"
+                };
+                draw_bars(display, &title, hint)?;
+
+                // remaining time of the phase and its progress
+
+                let remaining = self.pomodoro.remaining(now).num_milliseconds();
+                if self.pomodoro.is_running() {
+                    let until_change = match remaining % 1000 {
+                        0 => 1000,
+                        ms => ms,
+                    };
+                    self.request_redraw(now + chrono::Duration::milliseconds(until_change));
+                }
+
+                let seconds = (remaining + 999) / 1000;
+                let time = format!("{:02}:{:02}", seconds / 60, seconds % 60);
+                Text::with_baseline(
+                    &time,
+                    Point::new(29, 12),
+                    MonoTextStyle::new(&FONT_SEG7_14X24, BinaryColor::On),
+                    Baseline::Top,
+                )
+                .draw(display)
+                .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+
+                draw_progress(display, 46, self.pomodoro.progress(now))?;
             }
         }
 
+        // indicators from right to left: unread message, active timers
+
+        let indicators = [
+            (self.inbox.unread, &icon::ENVELOPE),
+            (
+                self.countdown.is_active() && !matches!(self.view, View::Timer),
+                &icon::HOURGLASS,
+            ),
+            (
+                self.stopwatch.is_active() && !matches!(self.view, View::Stopwatch),
+                &icon::STOPWATCH,
+            ),
+            (
+                self.pomodoro.is_active() && !matches!(self.view, View::Pomodoro),
+                &icon::TOMATO,
+            ),
+        ];
+        let shown = indicators.iter().filter(|(shown, _)| *shown);
+        for (n, (_, indicator)) in shown.enumerate() {
+            let x = 113 - n as i32 * 11;
+            Rectangle::new(Point::new(x, 5), Size::new(11, 9))
+                .into_styled(PrimitiveStyle::with_fill(BinaryColor::Off))
+                .draw(display)
+                .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+            icon::draw(display, indicator, Point::new(x + 1, 6), BinaryColor::On)?;
+        }
+
         Ok(())
     }
 }
+
+/// Draws filled bars at the top and bottom with a title and a hint in the small font.
+fn draw_bars<D>(display: &mut D, title: &str, hint: &str) -> Result<()>
+where
+    D: DrawTarget<Color = BinaryColor>,
+    D::Error: Send + Sync + core::fmt::Debug + 'static,
+{
+    Rectangle::new(Point::new(0, 0), Size::new(128, 8))
+        .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+        .draw(display)
+        .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+    Rectangle::new(Point::new(0, 56), Size::new(128, 8))
+        .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+        .draw(display)
+        .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+
+    for (text, y) in [(title, 1), (hint, 57)] {
+        Text::with_baseline(
+            text,
+            Point::new(4, y),
+            MonoTextStyle::new(&FONT_4X6, BinaryColor::Off),
+            Baseline::Top,
+        )
+        .draw(display)
+        .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Draws one square per page into the bottom bar, filled for the current page.
+fn draw_pages<D>(display: &mut D, pages: usize, page: usize) -> Result<()>
+where
+    D: DrawTarget<Color = BinaryColor>,
+    D::Error: Send + Sync + core::fmt::Debug + 'static,
+{
+    let pages = pages as i32;
+    if pages < 2 {
+        return Ok(());
+    }
+
+    for n in 0..pages {
+        let style = if n as usize == page {
+            PrimitiveStyle::with_fill(BinaryColor::Off)
+        } else {
+            PrimitiveStyle::with_stroke(BinaryColor::Off, 1)
+        };
+        Rectangle::new(Point::new(124 - (pages - n) * 6, 58), Size::new(4, 4))
+            .into_styled(style)
+            .draw(display)
+            .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Draws a line with a dot at `progress`, from 0 to 1, like the clock's second indicator.
+fn draw_progress<D>(display: &mut D, y: i32, progress: f32) -> Result<()>
+where
+    D: DrawTarget<Color = BinaryColor>,
+    D::Error: Send + Sync + core::fmt::Debug + 'static,
+{
+    let start = Point::new(10, y);
+    let length = 108;
+    let height = 1;
+    Line::new(start, Point::new(start.x + length, start.y))
+        .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, height))
+        .draw(display)
+        .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+    let x = start.x + (length as f32 * progress) as i32;
+    Circle::with_center(Point::new(x, start.y), height + 4)
+        .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+        .draw(display)
+        .map_err(|e| anyhow::anyhow!("draw failed: {:?}", e))?;
+
+    Ok(())
+}
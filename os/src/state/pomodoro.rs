@@ -0,0 +1,251 @@
+use crate::{
+    pomodoro::{self, Session},
+    storage,
+};
+use chrono::{DateTime, Duration, Utc};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+
+const STORAGE_KEY: &str = "pomodoro";
+
+/// Sessions older than this are dropped from the local history.
+const KEEP_DAYS: i64 = 30;
+
+/// Seconds the buzzer beeps and the screen stays lit after switching between focus and break.
+const ALERT_SECONDS: i64 = 3;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Phase {
+    Focus,
+    Break,
+    LongBreak,
+}
+
+/// Focus sessions, and the ones the server has not received yet.
+#[derive(Default, Serialize, Deserialize)]
+struct History {
+    sessions: Vec<Session>,
+    unreported: Vec<Session>,
+}
+
+/// Pomodoro timer alternating focus blocks and breaks, keeps running in the background.
+pub struct Pomodoro {
+    pub phase: Phase,
+    /// Focus blocks completed since the last reset.
+    pub cycles: u32,
+
+    focus_minutes: i64,
+    break_minutes: i64,
+    long_break_minutes: i64,
+    long_break_every: u32,
+    dim_after: Duration,
+    report: bool,
+
+    started_at: Option<DateTime<Utc>>,
+    elapsed: Duration,
+    switched_at: Option<DateTime<Utc>>,
+    last_input: DateTime<Utc>,
+    history: History,
+}
+
+impl Pomodoro {
+    pub fn load() -> Self {
+        let minutes = |name: &str, default: i64| {
+            std::env::var(name)
+                .unwrap_or(default.to_string())
+                .parse::<i64>()
+                .unwrap_or(default)
+                .max(1)
+        };
+
+        Pomodoro {
+            phase: Phase::Focus,
+            cycles: 0,
+
+            focus_minutes: minutes("POMODORO_FOCUS_MINUTES", 25),
+            break_minutes: minutes("POMODORO_BREAK_MINUTES", 5),
+            long_break_minutes: minutes("POMODORO_LONG_BREAK_MINUTES", 15),
+            long_break_every: minutes("POMODORO_LONG_BREAK_EVERY", 4) as u32,
+            dim_after: Duration::seconds(
+                std::env::var("POMODORO_DIM_SECONDS")
+                    .unwrap_or("10".into())
+                    .parse::<i64>()
+                    .unwrap_or(10),
+            ),
+            report: std::env::var("POMODORO_REPORT")
+                .unwrap_or("false".into())
+                .parse::<bool>()
+                .unwrap_or(false),
+
+            started_at: None,
+            elapsed: Duration::zero(),
+            switched_at: None,
+            last_input: Utc::now(),
+            history: storage::load(STORAGE_KEY).unwrap_or_default(),
+        }
+    }
+
+    /// Starts, pauses or resumes the current phase.
+    pub fn press(&mut self, now: DateTime<Utc>) {
+        match self.started_at.take() {
+            Some(started_at) => {
+                info!("pause pomodoro");
+                self.elapsed += now - started_at;
+            }
+            None => {
+                info!("start pomodoro");
+                self.started_at = Some(now);
+            }
+        }
+    }
+
+    /// Stops and goes back to the first focus block, logging the one in progress.
+    pub fn reset(&mut self, now: DateTime<Utc>) {
+        info!("reset pomodoro");
+        let focused = self.elapsed_in_phase(now);
+        if self.phase == Phase::Focus && focused >= Duration::minutes(1) {
+            self.log(now - focused, focused.num_minutes(), false);
+        }
+
+        self.phase = Phase::Focus;
+        self.cycles = 0;
+        self.started_at = None;
+        self.elapsed = Duration::zero();
+        self.switched_at = None;
+    }
+
+    /// Moves on to the next phase once the current one is over, returns true if it did.
+    pub fn check(&mut self, now: DateTime<Utc>) -> bool {
+        let Some(started_at) = self.started_at else {
+            return false;
+        };
+        let length = self.length();
+        if self.elapsed_in_phase(now) < length {
+            return false;
+        }
+
+        let ended_at = started_at + length - self.elapsed;
+        self.phase = match self.phase {
+            Phase::Focus => {
+                self.log(ended_at - length, length.num_minutes(), true);
+                self.cycles += 1;
+                if self.cycles.is_multiple_of(self.long_break_every) {
+                    Phase::LongBreak
+                } else {
+                    Phase::Break
+                }
+            }
+            Phase::Break | Phase::LongBreak => Phase::Focus,
+        };
+        info!("pomodoro switched after {} cycles", self.cycles);
+
+        self.started_at = Some(ended_at);
+        self.elapsed = Duration::zero();
+        self.switched_at = Some(now);
+        true
+    }
+
+    /// Length of the current phase.
+    pub fn length(&self) -> Duration {
+        Duration::minutes(match self.phase {
+            Phase::Focus => self.focus_minutes,
+            Phase::Break => self.break_minutes,
+            Phase::LongBreak => self.long_break_minutes,
+        })
+    }
+
+    pub fn remaining(&self, now: DateTime<Utc>) -> Duration {
+        (self.length() - self.elapsed_in_phase(now)).max(Duration::zero())
+    }
+
+    /// Share of the current phase that is over, from 0 to 1.
+    pub fn progress(&self, now: DateTime<Utc>) -> f32 {
+        let length = self.length().num_milliseconds() as f32;
+        (self.elapsed_in_phase(now).num_milliseconds() as f32 / length).min(1.0)
+    }
+
+    fn elapsed_in_phase(&self, now: DateTime<Utc>) -> Duration {
+        self.elapsed + self.started_at.map_or(Duration::zero(), |at| now - at)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.started_at.is_some()
+    }
+
+    /// Running or paused, but not reset.
+    pub fn is_active(&self) -> bool {
+        self.is_running() || self.elapsed > Duration::zero() || self.cycles > 0
+    }
+
+    /// Whether the phase just switched, to beep and show it.
+    pub fn is_alerting(&self, now: DateTime<Utc>) -> bool {
+        self.switched_at
+            .is_some_and(|at| now - at < Duration::seconds(ALERT_SECONDS))
+    }
+
+    /// Keeps the screen lit for a while after the dial was used.
+    pub fn touch(&mut self, now: DateTime<Utc>) {
+        self.last_input = now;
+    }
+
+    /// During focus blocks the screen dims down once the dial is left alone.
+    pub fn is_dimmed(&self, now: DateTime<Utc>) -> bool {
+        self.is_running()
+            && self.phase == Phase::Focus
+            && now - self.last_input >= self.dim_after
+            && !self.is_alerting(now)
+    }
+
+    /// When the screen dims during the current focus block.
+    pub fn dims_at(&self) -> Option<DateTime<Utc>> {
+        if !self.is_running() || self.phase != Phase::Focus {
+            return None;
+        }
+
+        let alert_over = self
+            .switched_at
+            .map(|at| at + Duration::seconds(ALERT_SECONDS));
+        Some(self.last_input + self.dim_after).max(alert_over)
+    }
+
+    // history
+
+    fn log(&mut self, started_at: DateTime<Utc>, minutes: i64, completed: bool) {
+        info!("log focus session of {minutes} minutes");
+        let session = Session {
+            started_at,
+            minutes,
+            completed,
+        };
+
+        let keep_from = Utc::now() - Duration::days(KEEP_DAYS);
+        self.history.sessions.retain(|s| s.started_at >= keep_from);
+        self.history.sessions.push(session.clone());
+        if self.report {
+            self.history.unreported.push(session);
+        }
+        self.save();
+        self.send_reports();
+    }
+
+    /// Sends unreported sessions to the server, retried on every fetch until it accepts them.
+    pub fn send_reports(&mut self) {
+        if !self.report || self.history.unreported.is_empty() {
+            return;
+        }
+
+        match pomodoro::report(&self.history.unreported) {
+            Ok(()) => {
+                self.history.unreported.clear();
+                self.save();
+            }
+            Err(err) => error!("failed to report focus sessions: {err:?}"),
+        }
+    }
+
+    fn save(&self) {
+        if let Err(err) = storage::save(STORAGE_KEY, &self.history) {
+            error!("failed to save focus sessions: {err:?}");
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use chrono::{DateTime, Duration, Utc};
+use log::info;
+
+/// An expired countdown stops ringing on its own after this many seconds.
+const RING_SECONDS: i64 = 60;
+const MAX_MINUTES: i64 = 99;
+
+/// Countdown with minutes set on the dial, keeps running in the background.
+pub struct Countdown {
+    pub minutes: i64,
+    /// Turning the dial changes the minutes instead of the view.
+    pub setting: bool,
+
+    started_at: Option<DateTime<Utc>>,
+    elapsed: Duration,
+    expired_at: Option<DateTime<Utc>>,
+}
+
+impl Countdown {
+    pub fn new() -> Self {
+        Countdown {
+            minutes: 5,
+            setting: false,
+
+            started_at: None,
+            elapsed: Duration::zero(),
+            expired_at: None,
+        }
+    }
+
+    /// Sets, starts, pauses or resumes the countdown depending on where it is.
+    pub fn press(&mut self, now: DateTime<Utc>) {
+        if self.setting {
+            info!("start countdown of {} minutes", self.minutes);
+            self.setting = false;
+            self.started_at = Some(now);
+        } else if let Some(started_at) = self.started_at.take() {
+            info!("pause countdown");
+            self.elapsed += now - started_at;
+        } else if self.elapsed > Duration::zero() {
+            info!("resume countdown");
+            self.started_at = Some(now);
+        } else {
+            self.setting = true;
+        }
+    }
+
+    pub fn turn(&mut self, delta: i64) {
+        self.minutes = (self.minutes + delta).clamp(1, MAX_MINUTES);
+    }
+
+    pub fn reset(&mut self) {
+        info!("reset countdown");
+        self.setting = false;
+        self.started_at = None;
+        self.elapsed = Duration::zero();
+        self.expired_at = None;
+    }
+
+    pub fn remaining(&self, now: DateTime<Utc>) -> Duration {
+        let elapsed = self.elapsed + self.started_at.map_or(Duration::zero(), |at| now - at);
+        (Duration::minutes(self.minutes) - elapsed).max(Duration::zero())
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.started_at.is_some()
+    }
+
+    /// Running or paused, but not reset.
+    pub fn is_active(&self) -> bool {
+        self.is_running() || self.elapsed > Duration::zero()
+    }
+
+    /// Starts ringing when the countdown ran out, returns true if it started.
+    pub fn check(&mut self, now: DateTime<Utc>) -> bool {
+        if let Some(at) = self.expired_at
+            && now - at > Duration::seconds(RING_SECONDS)
+        {
+            info!("countdown alert timed out");
+            self.expired_at = None;
+        }
+
+        if !self.is_running() || self.remaining(now) > Duration::zero() {
+            return false;
+        }
+
+        info!("countdown expired");
+        self.reset();
+        self.expired_at = Some(now);
+        true
+    }
+
+    pub fn is_ringing(&self) -> bool {
+        self.expired_at.is_some()
+    }
+
+    pub fn dismiss(&mut self) {
+        self.expired_at = None;
+    }
+}
+
+/// Stopwatch that keeps running in the background.
+pub struct Stopwatch {
+    started_at: Option<DateTime<Utc>>,
+    elapsed: Duration,
+}
+
+impl Stopwatch {
+    pub fn new() -> Self {
+        Stopwatch {
+            started_at: None,
+            elapsed: Duration::zero(),
+        }
+    }
+
+    pub fn toggle(&mut self, now: DateTime<Utc>) {
+        match self.started_at.take() {
+            Some(started_at) => {
+                info!("stop stopwatch");
+                self.elapsed += now - started_at;
+            }
+            None => {
+                info!("start stopwatch");
+                self.started_at = Some(now);
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        info!("reset stopwatch");
+        self.started_at = None;
+        self.elapsed = Duration::zero();
+    }
+
+    pub fn elapsed(&self, now: DateTime<Utc>) -> Duration {
+        self.elapsed + self.started_at.map_or(Duration::zero(), |at| now - at)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.started_at.is_some()
+    }
+
+    /// Running or stopped, but not reset.
+    pub fn is_active(&self) -> bool {
+        self.is_running() || self.elapsed > Duration::zero()
+    }
+}
@@ -0,0 +1,114 @@
+use crate::frame::{self, Frame};
+use embedded_graphics::prelude::*;
+use log::warn;
+
+/// 4x4 ordered dither thresholds, so a dissolve looks even without randomness.
+const BAYER: [[u32; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+#[derive(Clone, Copy)]
+pub enum Kind {
+    Slide,
+    Wipe,
+    Dissolve,
+}
+
+/// Where the incoming view comes from, matching the dial direction.
+#[derive(Clone, Copy)]
+pub enum Direction {
+    Left,
+    Right,
+}
+
+pub struct Config {
+    kind: Option<Kind>,
+    frames: u32,
+}
+
+impl Config {
+    pub fn from_env() -> Self {
+        let kind = match std::env::var("TRANSITION")
+            .unwrap_or("slide".into())
+            .to_lowercase()
+            .as_str()
+        {
+            "slide" => Some(Kind::Slide),
+            "wipe" => Some(Kind::Wipe),
+            "dissolve" => Some(Kind::Dissolve),
+            "none" => None,
+            other => {
+                warn!("unknown transition {other}, falling back to none");
+                None
+            }
+        };
+        let frames = std::env::var("TRANSITION_FRAMES")
+            .unwrap_or("8".into())
+            .parse::<u32>()
+            .unwrap_or(8);
+
+        Config { kind, frames }
+    }
+
+    /// Starts a transition away from `from`, or returns `None` if transitions are disabled.
+    pub fn start(&self, from: Frame, direction: Direction) -> Option<Transition> {
+        match self.kind {
+            Some(kind) if self.frames > 0 => Some(Transition {
+                from,
+                kind,
+                direction,
+                frame: 0,
+                frames: self.frames,
+            }),
+            _ => None,
+        }
+    }
+}
+
+pub struct Transition {
+    from: Frame,
+    kind: Kind,
+    direction: Direction,
+    frame: u32,
+    frames: u32,
+}
+
+impl Transition {
+    /// Composites the outgoing frame with `to` for the current step and advances by one step.
+    pub fn step(&mut self, to: &Frame) -> Frame {
+        self.frame += 1;
+
+        let width = frame::WIDTH as i32;
+        let offset = width * self.frame as i32 / self.frames as i32;
+        let mut out = Frame::new();
+
+        for p in out.bounding_box().points() {
+            let color = match (self.kind, self.direction) {
+                (Kind::Slide, Direction::Left) => match p.x + offset {
+                    x if x < width => self.from.get(Point::new(x, p.y)),
+                    x => to.get(Point::new(x - width, p.y)),
+                },
+                (Kind::Slide, Direction::Right) => match p.x - offset {
+                    x if x >= 0 => self.from.get(Point::new(x, p.y)),
+                    x => to.get(Point::new(x + width, p.y)),
+                },
+                (Kind::Wipe, Direction::Left) if p.x >= width - offset => to.get(p),
+                (Kind::Wipe, Direction::Right) if p.x < offset => to.get(p),
+                (Kind::Wipe, _) => self.from.get(p),
+                (Kind::Dissolve, _) => {
+                    let threshold = BAYER[p.y as usize % 4][p.x as usize % 4];
+                    if threshold * self.frames < 16 * self.frame {
+                        to.get(p)
+                    } else {
+                        self.from.get(p)
+                    }
+                }
+            };
+            out.set(p, color);
+        }
+
+        out
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.frame >= self.frames
+    }
+}
@@ -1,23 +1,62 @@
+#[derive(Clone, Copy, PartialEq)]
 pub enum View {
     Clock,
+    WorldClock,
     Weather,
+    Departures,
     Mailbox,
+    News,
+    Agenda,
+    Alarm,
+    Timer,
+    Stopwatch,
+    Pomodoro,
 }
 
 impl View {
+    /// All views in the order the dial goes through them.
+    pub const ALL: [View; 11] = [
+        View::Clock,
+        View::WorldClock,
+        View::Weather,
+        View::Departures,
+        View::Mailbox,
+        View::News,
+        View::Agenda,
+        View::Alarm,
+        View::Timer,
+        View::Stopwatch,
+        View::Pomodoro,
+    ];
+
     pub fn next(&self) -> Self {
-        match self {
-            View::Clock => View::Weather,
-            View::Weather => View::Mailbox,
-            View::Mailbox => View::Clock,
-        }
+        let index = View::ALL.iter().position(|view| view == self).unwrap_or(0);
+        View::ALL[(index + 1) % View::ALL.len()]
     }
 
     pub fn previous(&self) -> Self {
+        let index = View::ALL.iter().position(|view| view == self).unwrap_or(0);
+        View::ALL[(index + View::ALL.len() - 1) % View::ALL.len()]
+    }
+
+    /// Name used by remote control, e.g. `world_clock`.
+    pub fn name(&self) -> &'static str {
         match self {
-            View::Mailbox => View::Weather,
-            View::Weather => View::Clock,
-            View::Clock => View::Mailbox,
+            View::Clock => "clock",
+            View::WorldClock => "world_clock",
+            View::Weather => "weather",
+            View::Departures => "departures",
+            View::Mailbox => "mailbox",
+            View::News => "news",
+            View::Agenda => "agenda",
+            View::Alarm => "alarm",
+            View::Timer => "timer",
+            View::Stopwatch => "stopwatch",
+            View::Pomodoro => "pomodoro",
         }
     }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        View::ALL.into_iter().find(|view| view.name() == name)
+    }
 }
@@ -0,0 +1,67 @@
+use crate::storage;
+use chrono_tz::Tz;
+use log::{error, info, warn};
+
+const STORAGE_KEY: &str = "home_timezone";
+const MAX_ZONES: usize = 4;
+
+/// Time zones of the world clock, the day offsets are shown relative to the one that is home.
+/// Alarms, the agenda and the clock stay in `TIMEZONE`, whichever zone is home.
+pub struct WorldClock {
+    pub zones: Vec<Tz>,
+    pub home: usize,
+}
+
+impl WorldClock {
+    /// Reads the zones from `WORLD_CLOCK`, with `timezone` first unless it is listed already.
+    pub fn load(timezone: Tz) -> Self {
+        let mut zones = vec![timezone];
+        for name in std::env::var("WORLD_CLOCK").unwrap_or_default().split(',') {
+            let name = name.trim();
+            if name.is_empty() {
+                continue;
+            }
+
+            match name.parse::<Tz>() {
+                Ok(zone) if !zones.contains(&zone) => zones.push(zone),
+                Ok(_) => {}
+                Err(err) => warn!("skip world clock zone {name}: {err}"),
+            }
+        }
+        if zones.len() > MAX_ZONES {
+            warn!("world clock shows the first {MAX_ZONES} zones only");
+            zones.truncate(MAX_ZONES);
+        }
+
+        // the home zone picked on the dial survives restarts
+
+        let home = storage::load::<String>(STORAGE_KEY)
+            .and_then(|name| zones.iter().position(|zone| zone.name() == name))
+            .unwrap_or(0);
+
+        WorldClock { zones, home }
+    }
+
+    pub fn home(&self) -> Tz {
+        self.zones[self.home]
+    }
+
+    /// Makes the next zone home.
+    pub fn cycle_home(&mut self) {
+        self.home = (self.home + 1) % self.zones.len();
+        info!("home timezone is now {}", self.home());
+
+        if let Err(err) = storage::save(STORAGE_KEY, &self.home().name()) {
+            error!("failed to save home timezone: {err:?}");
+        }
+    }
+}
+
+/// City part of a zone name, e.g. `New York` for `America/New_York`.
+pub fn city(zone: Tz) -> String {
+    zone.name()
+        .rsplit('/')
+        .next()
+        .unwrap_or(zone.name())
+        .replace('_', " ")
+}
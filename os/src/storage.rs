@@ -0,0 +1,40 @@
+use anyhow::{Context, Result};
+use log::{trace, warn};
+use serde::{Serialize, de::DeserializeOwned};
+use std::{env, fs, path::PathBuf};
+
+fn path(name: &str) -> PathBuf {
+    let dir = env::var("DATA_DIR").unwrap_or(".".into());
+    PathBuf::from(dir).join(format!("{name}.json"))
+}
+
+pub fn load<T: DeserializeOwned>(name: &str) -> Option<T> {
+    let path = path(name);
+    let data = fs::read_to_string(&path).ok()?;
+
+    match serde_json::from_str(&data) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            warn!("fails to parse {}: {err:?}", path.display());
+            None
+        }
+    }
+}
+
+pub fn save<T: Serialize>(name: &str, value: &T) -> Result<()> {
+    let path = path(name);
+    trace!("save {}", path.display());
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).context("fails to create data dir")?;
+    }
+
+    // write to a temp file first so a power cut never leaves a half written file
+
+    let tmp = path.with_extension("json.tmp");
+    let data = serde_json::to_string(value).context("fails to serialize data")?;
+    fs::write(&tmp, data).context("fails to write data")?;
+    fs::rename(&tmp, &path).context("fails to replace data")?;
+
+    Ok(())
+}
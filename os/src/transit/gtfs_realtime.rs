@@ -0,0 +1,203 @@
+use super::Departure;
+use anyhow::{Result, bail};
+use chrono::DateTime;
+
+// field numbers of the messages in gtfs-realtime.proto that are read here
+
+const FEED_ENTITY: u32 = 2;
+const ENTITY_TRIP_UPDATE: u32 = 3;
+const TRIP_UPDATE_TRIP: u32 = 1;
+const TRIP_UPDATE_STOP_TIME_UPDATE: u32 = 2;
+const TRIP_UPDATE_TRIP_PROPERTIES: u32 = 6;
+const TRIP_ROUTE_ID: u32 = 5;
+const TRIP_SCHEDULE_RELATIONSHIP: u32 = 4;
+const TRIP_PROPERTIES_HEADSIGN: u32 = 5;
+const STOP_TIME_ARRIVAL: u32 = 2;
+const STOP_TIME_DEPARTURE: u32 = 3;
+const STOP_TIME_STOP_ID: u32 = 4;
+const STOP_TIME_SCHEDULE_RELATIONSHIP: u32 = 5;
+const EVENT_TIME: u32 = 2;
+
+const TRIP_CANCELED: u64 = 3;
+const STOP_SKIPPED: u64 = 1;
+
+/// Reads the departures at `stop` from a GTFS-realtime `FeedMessage` with trip updates.
+///
+/// The feed has no destinations, only the optional experimental trip headsign is shown.
+pub fn parse(feed: &[u8], stop: &str) -> Result<Vec<Departure>> {
+    let mut departures = Vec::new();
+    for entity in messages(feed, FEED_ENTITY)? {
+        for trip_update in messages(entity, ENTITY_TRIP_UPDATE)? {
+            let trip = messages(trip_update, TRIP_UPDATE_TRIP)?;
+            let trip = trip.first().copied().unwrap_or_default();
+            if varint(trip, TRIP_SCHEDULE_RELATIONSHIP)? == Some(TRIP_CANCELED) {
+                continue;
+            }
+
+            let line = string(trip, TRIP_ROUTE_ID)?.unwrap_or_default();
+            let destination = match messages(trip_update, TRIP_UPDATE_TRIP_PROPERTIES)?.first() {
+                Some(properties) => string(properties, TRIP_PROPERTIES_HEADSIGN)?,
+                None => None,
+            };
+
+            for update in messages(trip_update, TRIP_UPDATE_STOP_TIME_UPDATE)? {
+                if string(update, STOP_TIME_STOP_ID)?.as_deref() != Some(stop)
+                    || varint(update, STOP_TIME_SCHEDULE_RELATIONSHIP)? == Some(STOP_SKIPPED)
+                {
+                    continue;
+                }
+
+                // the departure time, or the arrival time at the last stop of a trip
+
+                let mut events = messages(update, STOP_TIME_DEPARTURE)?;
+                events.extend(messages(update, STOP_TIME_ARRIVAL)?);
+                let mut time = None;
+                for event in events {
+                    time = varint(event, EVENT_TIME)?;
+                    if time.is_some() {
+                        break;
+                    }
+                }
+                let Some(time) = time.and_then(|time| DateTime::from_timestamp(time as i64, 0))
+                else {
+                    continue;
+                };
+
+                departures.push(Departure {
+                    line: line.clone(),
+                    destination: destination.clone().unwrap_or_default(),
+                    time,
+                });
+            }
+        }
+    }
+
+    Ok(departures)
+}
+
+enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+/// Splits a protobuf message into its fields.
+fn fields(mut data: &[u8]) -> Result<Vec<(u32, Value<'_>)>> {
+    let mut fields = Vec::new();
+    while !data.is_empty() {
+        let key = read_varint(&mut data)?;
+        let value = match key & 0x7 {
+            0 => Value::Varint(read_varint(&mut data)?),
+            1 | 5 => {
+                let len = if key & 0x7 == 1 { 8 } else { 4 };
+                if data.len() < len {
+                    bail!("truncated fixed field");
+                }
+                data = &data[len..];
+                Value::Fixed
+            }
+            2 => {
+                let len = read_varint(&mut data)? as usize;
+                if data.len() < len {
+                    bail!("truncated length delimited field");
+                }
+                let (bytes, rest) = data.split_at(len);
+                data = rest;
+                Value::Bytes(bytes)
+            }
+            wire_type => bail!("unsupported wire type {wire_type}"),
+        };
+        fields.push(((key >> 3) as u32, value));
+    }
+    Ok(fields)
+}
+
+fn read_varint(data: &mut &[u8]) -> Result<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let Some((byte, rest)) = data.split_first() else {
+            bail!("truncated varint");
+        };
+        *data = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    bail!("varint too long")
+}
+
+/// All embedded messages in the field `number`.
+fn messages(data: &[u8], number: u32) -> Result<Vec<&[u8]>> {
+    Ok(fields(data)?
+        .into_iter()
+        .filter_map(|field| match field {
+            (n, Value::Bytes(bytes)) if n == number => Some(bytes),
+            _ => None,
+        })
+        .collect())
+}
+
+fn varint(data: &[u8], number: u32) -> Result<Option<u64>> {
+    Ok(fields(data)?.into_iter().find_map(|field| match field {
+        (n, Value::Varint(value)) if n == number => Some(value),
+        _ => None,
+    }))
+}
+
+fn string(data: &[u8], number: u32) -> Result<Option<String>> {
+    let bytes = messages(data, number)?;
+    Ok(bytes
+        .first()
+        .map(|bytes| String::from_utf8_lossy(bytes).into_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Trip updates of U2 to Pankow at stops A and B, a canceled M10 at A, an S1 at B that
+    /// skips A, an RE1 ending at A with only an arrival time and a vehicle position.
+    const FEED: &[u8] = include_bytes!("../../fixtures/transit/trip-updates.pb");
+    const NOW: i64 = 1_792_310_400;
+
+    fn departures(stop: &str) -> Vec<(String, String, i64)> {
+        parse(FEED, stop)
+            .unwrap()
+            .into_iter()
+            .map(|d| (d.line, d.destination, d.time.timestamp() - NOW))
+            .collect()
+    }
+
+    #[test]
+    fn reads_the_departures_at_a_stop() {
+        assert_eq!(
+            departures("A"),
+            [
+                ("U2".to_string(), "Pankow".to_string(), 120),
+                ("RE1".to_string(), String::new(), 600),
+            ]
+        );
+        assert_eq!(
+            departures("B"),
+            [
+                ("U2".to_string(), "Pankow".to_string(), 240),
+                ("S1".to_string(), String::new(), 60),
+            ]
+        );
+        assert!(departures("Z").is_empty());
+    }
+
+    #[test]
+    fn reads_varints() {
+        let mut data = &[0xac, 0x02, 0x01][..];
+        assert_eq!(read_varint(&mut data).unwrap(), 300);
+        assert_eq!(data, [0x01]);
+    }
+
+    #[test]
+    fn fails_on_truncated_feeds() {
+        assert!(parse(&FEED[..FEED.len() - 3], "A").is_err());
+        assert!(read_varint(&mut &[0x80][..]).is_err());
+    }
+}
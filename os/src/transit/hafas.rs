@@ -0,0 +1,91 @@
+use super::Departure;
+use anyhow::Result;
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::Deserialize;
+
+/// Response of `/stops/{id}/departures`, wrapped in an object since v6 of transport.rest.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Response {
+    Wrapped { departures: Vec<Entry> },
+    List(Vec<Entry>),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    when: Option<DateTime<FixedOffset>>,
+    planned_when: Option<DateTime<FixedOffset>>,
+    direction: Option<String>,
+    line: Option<Line>,
+    #[serde(default)]
+    cancelled: bool,
+}
+
+#[derive(Deserialize)]
+struct Line {
+    name: Option<String>,
+}
+
+pub fn parse(json: &str) -> Result<Vec<Departure>> {
+    let entries = match serde_json::from_str(json)? {
+        Response::Wrapped { departures } => departures,
+        Response::List(departures) => departures,
+    };
+
+    Ok(entries
+        .into_iter()
+        .filter(|entry| !entry.cancelled)
+        .filter_map(|entry| {
+            Some(Departure {
+                line: entry.line.and_then(|line| line.name).unwrap_or_default(),
+                destination: entry.direction.unwrap_or_default(),
+                time: entry.when.or(entry.planned_when)?.with_timezone(&Utc),
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn departures(json: &str) -> Vec<(String, String, String)> {
+        parse(json)
+            .unwrap()
+            .into_iter()
+            .map(|d| (d.line, d.destination, d.time.format("%H:%M").to_string()))
+            .collect()
+    }
+
+    fn departure(line: &str, destination: &str, time: &str) -> (String, String, String) {
+        (line.to_string(), destination.to_string(), time.to_string())
+    }
+
+    #[test]
+    fn parses_wrapped_departures() {
+        assert_eq!(
+            departures(include_str!("../../fixtures/transit/departures.json")),
+            [
+                departure("U2", "S+U Pankow", "08:02"),
+                departure("M10", "Hauptbahnhof", "08:05"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_a_list_of_departures() {
+        assert_eq!(
+            departures(include_str!("../../fixtures/transit/departures-list.json")),
+            [
+                departure("U1", "Warschauer Str.", "08:10"),
+                departure("", "", "08:12"),
+            ]
+        );
+    }
+
+    #[test]
+    fn fails_on_other_json() {
+        assert!(parse(r#"{"error": "not found"}"#).is_err());
+    }
+}
@@ -0,0 +1,164 @@
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use log::{debug, trace};
+use std::env;
+
+mod gtfs_realtime;
+mod hafas;
+
+/// Departures kept after a fetch, the view shows the first `TRANSIT_DEPARTURES` still to come.
+const MAX_DEPARTURES: usize = 20;
+
+#[derive(Debug, Clone)]
+pub struct Departure {
+    pub line: String,
+    pub destination: String,
+    /// Expected departure, including delays.
+    pub time: DateTime<Utc>,
+}
+
+/// Whether a departure source is configured in `TRANSIT_URL`.
+pub fn is_configured() -> bool {
+    env::var("TRANSIT_URL").is_ok_and(|url| !url.trim().is_empty())
+}
+
+/// Number of departures shown at once, from `TRANSIT_DEPARTURES`.
+pub fn count() -> usize {
+    env::var("TRANSIT_DEPARTURES")
+        .unwrap_or("5".into())
+        .parse::<usize>()
+        .unwrap_or(5)
+        .clamp(1, 5)
+}
+
+/// Fetches the next departures at `TRANSIT_STOP`, from a JSON departures endpoint like the
+/// HAFAS based transport.rest APIs or from a GTFS-realtime trip updates feed.
+pub fn fetch() -> Result<Vec<Departure>> {
+    let url = env::var("TRANSIT_URL").context("TRANSIT_URL env var not set")?;
+    let stop = env::var("TRANSIT_STOP").context("TRANSIT_STOP env var not set")?;
+    let format = env::var("TRANSIT_FORMAT").unwrap_or("json".into());
+
+    fetch_from(&url, &stop, &format, Utc::now())
+}
+
+/// Fetches the departures at `stop` after `now` from `url`, where `{stop}` is replaced by the
+/// stop, in `format`, `json` or `gtfs-rt`.
+fn fetch_from(url: &str, stop: &str, format: &str, now: DateTime<Utc>) -> Result<Vec<Departure>> {
+    let url = url.replace("{stop}", stop);
+    debug!("fetch departures at stop {stop}");
+    let response = reqwest::blocking::get(url)
+        .and_then(|response| response.error_for_status())
+        .context("fails to fetch departures")?;
+
+    let mut departures = match format {
+        "json" => {
+            let json = response.text().context("fails to read departures")?;
+            hafas::parse(&json).context("fails to parse departures")?
+        }
+        "gtfs-rt" => {
+            let feed = response.bytes().context("fails to read departures")?;
+            gtfs_realtime::parse(&feed, stop).context("fails to parse departures")?
+        }
+        format => bail!("unknown transit format {format}"),
+    };
+    trace!("departures {:#?}", departures);
+
+    departures.retain(|departure| departure.time >= now);
+    departures.sort_by_key(|departure| departure.time);
+    departures.truncate(MAX_DEPARTURES);
+    Ok(departures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        sync::mpsc::{self, Receiver},
+        thread,
+    };
+    use tiny_http::{Response, Server};
+
+    /// Serves `body` once on a local port, returns its URL and the path that was requested.
+    fn serve(body: Vec<u8>, status: u16) -> (String, Receiver<String>) {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", server.server_addr());
+        let (sender, requested) = mpsc::channel();
+        thread::spawn(move || {
+            let request = server.recv().unwrap();
+            sender.send(request.url().to_string()).ok();
+            request
+                .respond(Response::from_data(body).with_status_code(status))
+                .unwrap();
+        });
+        (url, requested)
+    }
+
+    fn utc(time: &str) -> DateTime<Utc> {
+        time.parse().unwrap()
+    }
+
+    fn lines(departures: &[Departure]) -> Vec<&str> {
+        departures.iter().map(|d| d.line.as_str()).collect()
+    }
+
+    #[test]
+    fn fetches_json_departures_of_the_stop() {
+        let json = include_bytes!("../../fixtures/transit/departures.json");
+        let (url, requested) = serve(json.to_vec(), 200);
+
+        let departures = fetch_from(
+            &format!("{url}/stops/{{stop}}/departures?duration=30"),
+            "900100003",
+            "json",
+            utc("2026-10-18T08:03:00Z"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            requested.recv().unwrap(),
+            "/stops/900100003/departures?duration=30"
+        );
+        // the U2 at 08:02 is gone already
+        assert_eq!(lines(&departures), ["M10"]);
+    }
+
+    #[test]
+    fn fetches_gtfs_realtime_departures_in_order() {
+        let feed = include_bytes!("../../fixtures/transit/trip-updates.pb");
+        let (url, _) = serve(feed.to_vec(), 200);
+
+        let departures = fetch_from(&url, "B", "gtfs-rt", utc("2026-10-18T08:00:00Z")).unwrap();
+
+        assert_eq!(lines(&departures), ["S1", "U2"]);
+        assert_eq!(departures[0].time, utc("2026-10-18T08:01:00Z"));
+    }
+
+    #[test]
+    fn keeps_the_next_departures_only() {
+        let json = (0..30)
+            .rev()
+            .map(|minute| {
+                format!(
+                    r#"{{"line": {{"name": "{minute}"}}, "when": "2026-10-18T08:{minute:02}:00Z"}}"#
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        let (url, _) = serve(format!("[{json}]").into_bytes(), 200);
+
+        let departures = fetch_from(&url, "1", "json", utc("2026-10-18T08:05:00Z")).unwrap();
+
+        assert_eq!(departures.len(), MAX_DEPARTURES);
+        assert_eq!(departures[0].line, "5");
+        assert_eq!(departures[MAX_DEPARTURES - 1].line, "24");
+    }
+
+    #[test]
+    fn fails_on_errors_and_unknown_formats() {
+        let (url, _) = serve(b"not found".to_vec(), 404);
+        assert!(fetch_from(&url, "1", "json", Utc::now()).is_err());
+
+        let (url, _) = serve(b"[]".to_vec(), 200);
+        assert!(fetch_from(&url, "1", "xml", Utc::now()).is_err());
+    }
+}
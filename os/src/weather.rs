@@ -38,6 +38,16 @@ pub struct HourlyData {
     pub wind_direction_10m: Vec<i32>,
     pub showers: Vec<f64>,
     pub surface_pressure: Vec<f64>,
+    pub cloud_cover: Vec<i32>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Condition {
+    Sun,
+    Cloud,
+    Rain,
+    Snow,
 }
 
 pub struct Weather {
@@ -48,6 +58,7 @@ pub struct Weather {
     pub wind_speed_km_h: f32,
     pub wind_direction_deg: f32,
     pub rain_in_x_hours: Option<usize>,
+    pub condition: Condition,
 }
 
 pub fn fetch() -> Result<Weather> {
@@ -75,7 +86,7 @@ pub fn fetch() -> Result<Weather> {
 
     debug!("fetch weather for latitude: {lat}, longitude: {lon}");
     let url = format!(
-        "https://api.open-meteo.com/v1/forecast?latitude={lat:.2}&longitude={lon:.2}&hourly=temperature_2m,relative_humidity_2m,rain,snowfall,wind_speed_10m,wind_direction_10m,showers,surface_pressure&visibility&start_date=2026-01-04&end_date={date}"
+        "https://api.open-meteo.com/v1/forecast?latitude={lat:.2}&longitude={lon:.2}&hourly=temperature_2m,relative_humidity_2m,rain,snowfall,wind_speed_10m,wind_direction_10m,showers,surface_pressure,cloud_cover&visibility&start_date=2026-01-04&end_date={date}"
     );
     let response = reqwest::blocking::get(url).context("fails to fetch weather data")?;
     let data: WeatherData = response.json().context("fails to parse weather data")?;
@@ -95,6 +106,16 @@ pub fn fetch() -> Result<Weather> {
         .find(|&(n, &rain)| n >= hour && rain > 0.0)
         .map(|(index, _)| index);
 
+    let condition = if data.hourly.snowfall[hour] > 0.0 {
+        Condition::Snow
+    } else if data.hourly.rain[hour] > 0.0 || data.hourly.showers[hour] > 0.0 {
+        Condition::Rain
+    } else if data.hourly.cloud_cover[hour] > 50 {
+        Condition::Cloud
+    } else {
+        Condition::Sun
+    };
+
     Ok(Weather {
         temperature: data.hourly.temperature_2m[hour] as f32,
         relative_humidity_percent: data.hourly.relative_humidity_2m[hour] as u8,
@@ -102,5 +123,6 @@ pub fn fetch() -> Result<Weather> {
         wind_speed_km_h: data.hourly.wind_speed_10m[hour] as f32,
         wind_direction_deg: data.hourly.wind_direction_10m[hour] as f32,
         rain_in_x_hours: next_rain_hour.map(|index| index - hour),
+        condition,
     })
 }
@@ -0,0 +1,597 @@
+//! Versioned JSON API under `/api/v1`, for scripts, pipelines and bots. The HTML form goes
+//! through the same operations.
+
+use crate::{
+    AppState, MAX_MESSAGE_LENGTH,
+    auth::{self, Caller},
+    error_page, lock,
+    storage::{Alarm, AuditEntry, DEFAULT_DEVICE, Device, Message, Role, Session, User},
+};
+use axum::{
+    Extension, Json, Router,
+    extract::{
+        Path, Query, State,
+        rejection::{JsonRejection, PathRejection},
+    },
+    http::StatusCode,
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{delete, get, put},
+};
+use chrono::{DateTime, NaiveDate, NaiveTime, Timelike, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+const DEFAULT_LIMIT: usize = 20;
+const MAX_LIMIT: usize = 100;
+
+pub fn router() -> Router<AppState> {
+    let admin = Router::new()
+        .route("/devices/:id", put(save_device).delete(remove_device))
+        .route("/audit", get(list_audit))
+        .route_layer(middleware::from_fn(auth::require_admin));
+    Router::new()
+        .route("/messages", get(list_messages).post(create_message))
+        .route("/messages/current", get(current_message))
+        .route("/alarms", get(list_alarms).post(create_alarm))
+        .route("/alarms/:id", delete(remove_alarm))
+        .route("/sessions", get(list_sessions))
+        .route("/devices", get(list_devices))
+        .merge(admin)
+        .fallback(not_found)
+}
+
+/// Error with a status code, sent as `{"error": ..., "detail": ...}` to API clients and as
+/// an error page to the form.
+pub struct ApiError {
+    status: StatusCode,
+    title: &'static str,
+    detail: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, title: &'static str, detail: impl Into<String>) -> Self {
+        ApiError {
+            status,
+            title,
+            detail: detail.into(),
+        }
+    }
+
+    pub fn into_page(self) -> Response {
+        error_page(self.status, self.title, &self.detail)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = serde_json::json!({ "error": self.title, "detail": self.detail });
+        (self.status, Json(body)).into_response()
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        ApiError::new(rejection.status(), "invalid body", rejection.body_text())
+    }
+}
+
+impl From<PathRejection> for ApiError {
+    fn from(rejection: PathRejection) -> Self {
+        ApiError::new(rejection.status(), "invalid path", rejection.body_text())
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(err: anyhow::Error) -> Self {
+        error!("Storage failed: {:?}", err);
+        ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "storage error",
+            err.to_string(),
+        )
+    }
+}
+
+async fn not_found() -> ApiError {
+    ApiError::new(StatusCode::NOT_FOUND, "not found", "no such endpoint")
+}
+
+/// `?limit=` of the history endpoints.
+#[derive(Deserialize)]
+struct Limit {
+    limit: Option<usize>,
+}
+
+impl Limit {
+    fn get(&self) -> usize {
+        limit(self.limit)
+    }
+}
+
+fn limit(limit: Option<usize>) -> usize {
+    limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT)
+}
+
+/// Adds a change to the audit history. The change itself already happened, so a failure
+/// here is only logged.
+pub fn record(state: &AppState, caller: &Caller, action: &str, detail: String) {
+    let entry = AuditEntry {
+        at: Utc::now(),
+        actor: caller.username.clone(),
+        action: action.to_string(),
+        detail,
+    };
+    if let Err(e) = state.store.record(&entry) {
+        error!("Failed to record {}: {:?}", action, e);
+    }
+}
+
+// messages
+
+#[derive(Serialize)]
+pub struct MessageBody {
+    device: String,
+    text: String,
+    version: String,
+    seen_at: Option<DateTime<Utc>>,
+}
+
+impl MessageBody {
+    fn new(device: &str, message: Message) -> Self {
+        MessageBody {
+            device: device.to_string(),
+            text: message.text,
+            version: message.version.to_string(),
+            seen_at: message.seen_at,
+        }
+    }
+}
+
+/// A message as sent, with the devices it went to.
+#[derive(Serialize)]
+pub struct SentMessage {
+    text: String,
+    version: String,
+    devices: Vec<String>,
+}
+
+/// Who gets a message. Nobody picked means every device.
+#[derive(Default, Deserialize)]
+pub struct Recipients {
+    #[serde(default)]
+    pub devices: Vec<String>,
+    #[serde(default)]
+    pub groups: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct NewMessage {
+    text: String,
+    #[serde(flatten)]
+    to: Recipients,
+}
+
+/// `?device=` and `?limit=` of the message endpoints, for the cube by default.
+#[derive(Deserialize)]
+struct Inbox {
+    device: Option<String>,
+    limit: Option<usize>,
+}
+
+impl Inbox {
+    fn device(&self) -> &str {
+        self.device.as_deref().unwrap_or(DEFAULT_DEVICE)
+    }
+}
+
+/// Puts a message in the inboxes of the recipients and pushes it to them.
+pub async fn send_message(
+    state: &AppState,
+    caller: &Caller,
+    text: &str,
+    to: Recipients,
+) -> Result<SentMessage, ApiError> {
+    if text.chars().count() > MAX_MESSAGE_LENGTH {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "message too long",
+            format!("maximum {} characters allowed", MAX_MESSAGE_LENGTH),
+        ));
+    }
+    let text = text.trim().to_string();
+    if text.is_empty() {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "message empty",
+            "the message needs some text",
+        ));
+    }
+
+    let devices = recipients(state, &to)?;
+    let message = Message {
+        text,
+        version: next_version(state, &devices)?,
+        seen_at: None,
+    };
+    for device in &devices {
+        state.store.set_message(device, &message)?;
+    }
+    info!("Message '{}' sent to {}", message.text, devices.join(", "));
+    record(
+        state,
+        caller,
+        "message.send",
+        format!("{} to {}", message.text, devices.join(", ")),
+    );
+    state.message_updates.send_replace(message.version);
+
+    Ok(SentMessage {
+        text: message.text,
+        version: message.version.to_string(),
+        devices,
+    })
+}
+
+/// Version for a new message, the time in milliseconds unless that is not above the last one
+/// sent or one of the recipients' current ones. Messages are stored by device and version, so
+/// two sends within a millisecond would replace one another.
+fn next_version(state: &AppState, devices: &[String]) -> Result<i64, ApiError> {
+    let mut stored = 0;
+    for device in devices {
+        if let Some(message) = state.store.message(device)? {
+            stored = stored.max(message.version);
+        }
+    }
+
+    let mut last = lock(&state.last_version);
+    *last = Utc::now().timestamp_millis().max(*last + 1).max(stored + 1);
+    Ok(*last)
+}
+
+/// Device ids of the recipients, every known device when nobody was picked.
+fn recipients(state: &AppState, to: &Recipients) -> Result<Vec<String>, ApiError> {
+    let known = state.store.devices()?;
+    if to.devices.is_empty() && to.groups.is_empty() {
+        if known.is_empty() {
+            return Ok(vec![DEFAULT_DEVICE.to_string()]);
+        }
+        return Ok(known.into_iter().map(|device| device.id).collect());
+    }
+
+    let mut devices = Vec::new();
+    for id in &to.devices {
+        if !known.iter().any(|device| &device.id == id) {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "unknown device",
+                id.clone(),
+            ));
+        }
+        devices.push(id.clone());
+    }
+    for group in &to.groups {
+        let members = known
+            .iter()
+            .filter(|device| device.groups.contains(group))
+            .map(|device| device.id.clone())
+            .collect::<Vec<_>>();
+        if members.is_empty() {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "unknown group",
+                group.clone(),
+            ));
+        }
+        devices.extend(members);
+    }
+    devices.sort();
+    devices.dedup();
+
+    Ok(devices)
+}
+
+/// Past messages of a device, the newest first. File storage only keeps the current one.
+async fn list_messages(
+    State(state): State<AppState>,
+    Query(inbox): Query<Inbox>,
+) -> Result<Json<Vec<MessageBody>>, ApiError> {
+    let device = inbox.device();
+    let messages = state.store.messages(device, limit(inbox.limit))?;
+    Ok(Json(
+        messages
+            .into_iter()
+            .map(|message| MessageBody::new(device, message))
+            .collect(),
+    ))
+}
+
+async fn current_message(
+    State(state): State<AppState>,
+    Query(inbox): Query<Inbox>,
+) -> Result<Json<MessageBody>, ApiError> {
+    let device = inbox.device();
+    match state.store.message(device)? {
+        Some(message) => Ok(Json(MessageBody::new(device, message))),
+        None => Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            "no message",
+            format!("{} got no message yet", device),
+        )),
+    }
+}
+
+async fn create_message(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    payload: Result<Json<NewMessage>, JsonRejection>,
+) -> Result<(StatusCode, Json<SentMessage>), ApiError> {
+    let Json(new) = payload?;
+    let message = send_message(&state, &caller, &new.text, new.to).await?;
+    Ok((StatusCode::CREATED, Json(message)))
+}
+
+// alarms
+
+/// Alarm with the id to delete it with.
+#[derive(Serialize)]
+pub struct AlarmBody {
+    id: i64,
+    #[serde(flatten)]
+    alarm: Alarm,
+}
+
+#[derive(Deserialize)]
+pub struct NewAlarm {
+    /// Time like `07:30`.
+    pub time: String,
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+    /// Date like `2026-10-24` for a one-off alarm, ignored when `days` are set.
+    #[serde(default)]
+    pub date: Option<String>,
+}
+
+pub async fn add_alarm(
+    state: &AppState,
+    caller: &Caller,
+    new: NewAlarm,
+) -> Result<AlarmBody, ApiError> {
+    let Ok(time) = NaiveTime::parse_from_str(&new.time, "%H:%M") else {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "invalid time",
+            new.time,
+        ));
+    };
+
+    // Alarms from the server are never disabled by the cube, so one-offs need a date
+    let date = new
+        .date
+        .as_deref()
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok());
+    let date = match date {
+        Some(date) if new.days.is_empty() => Some(date),
+        _ if new.days.is_empty() => {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "no day selected",
+                "pick weekdays to repeat on or a date",
+            ));
+        }
+        _ => None,
+    };
+
+    let alarm = Alarm {
+        hour: time.hour(),
+        minute: time.minute(),
+        days: new.days,
+        date,
+        enabled: true,
+    };
+    let id = state.store.add_alarm(&alarm)?;
+    info!("Alarm added at {}", time.format("%H:%M"));
+    record(state, caller, "alarm.add", time.format("%H:%M").to_string());
+
+    Ok(AlarmBody { id, alarm })
+}
+
+pub async fn delete_alarm(state: &AppState, caller: &Caller, id: i64) -> Result<(), ApiError> {
+    if !state.store.delete_alarm(id)? {
+        return Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            "alarm not found",
+            "it may have been deleted already",
+        ));
+    }
+    info!("Alarm deleted");
+    record(state, caller, "alarm.delete", id.to_string());
+
+    Ok(())
+}
+
+async fn list_alarms(State(state): State<AppState>) -> Result<Json<Vec<AlarmBody>>, ApiError> {
+    let alarms = state.store.alarms()?;
+    Ok(Json(
+        alarms
+            .into_iter()
+            .map(|(id, alarm)| AlarmBody { id, alarm })
+            .collect(),
+    ))
+}
+
+async fn create_alarm(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    payload: Result<Json<NewAlarm>, JsonRejection>,
+) -> Result<(StatusCode, Json<AlarmBody>), ApiError> {
+    let Json(new) = payload?;
+    let alarm = add_alarm(&state, &caller, new).await?;
+    Ok((StatusCode::CREATED, Json(alarm)))
+}
+
+async fn remove_alarm(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    id: Result<Path<i64>, PathRejection>,
+) -> Result<StatusCode, ApiError> {
+    let Path(id) = id?;
+    delete_alarm(&state, &caller, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// focus sessions
+
+async fn list_sessions(State(state): State<AppState>) -> Result<Json<Vec<Session>>, ApiError> {
+    Ok(Json(state.store.sessions()?))
+}
+
+// devices
+
+#[derive(Serialize)]
+struct DeviceBody {
+    id: String,
+    groups: Vec<String>,
+    /// Whether the device logs in with its own password.
+    has_password: bool,
+    /// Last time the device asked for its message.
+    last_seen: Option<DateTime<Utc>>,
+    /// Whether the device is connected for pushed messages right now.
+    subscribed: bool,
+}
+
+impl DeviceBody {
+    fn new(state: &AppState, device: Device, users: &[User]) -> Self {
+        let subscribed = state
+            .subscribers
+            .lock()
+            .is_ok_and(|subscribers| subscribers.contains_key(&device.id));
+        DeviceBody {
+            subscribed,
+            has_password: users
+                .iter()
+                .any(|user| user.username == device.id && user.role == Role::Device),
+            id: device.id,
+            groups: device.groups,
+            last_seen: device.last_seen,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct DeviceSettings {
+    #[serde(default)]
+    groups: Vec<String>,
+    /// New password for the device to log in with, the current one stays when missing.
+    password: Option<String>,
+}
+
+async fn list_devices(State(state): State<AppState>) -> Result<Json<Vec<DeviceBody>>, ApiError> {
+    let devices = state.store.devices()?;
+    let users = state.store.users()?;
+    Ok(Json(
+        devices
+            .into_iter()
+            .map(|device| DeviceBody::new(&state, device, &users))
+            .collect(),
+    ))
+}
+
+/// Adds a device or changes its groups and password. The password is the one of the user
+/// the device logs in as.
+async fn save_device(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    id: Result<Path<String>, PathRejection>,
+    payload: Result<Json<DeviceSettings>, JsonRejection>,
+) -> Result<Json<DeviceBody>, ApiError> {
+    let Path(id) = id?;
+    let Json(settings) = payload?;
+    if let Some(invalid) = std::iter::once(&id)
+        .chain(&settings.groups)
+        .find(|id| !auth::is_valid_id(id))
+    {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "invalid name",
+            format!(
+                "'{}' should be letters, digits, - and _ only, up to 32",
+                invalid
+            ),
+        ));
+    }
+
+    if let Some(password) = settings.password {
+        if password.is_empty() {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "password empty",
+                "leave the password out to keep the current one",
+            ));
+        }
+        if state
+            .store
+            .user(&id)?
+            .is_some_and(|user| user.role != Role::Device)
+        {
+            return Err(ApiError::new(
+                StatusCode::CONFLICT,
+                "username taken",
+                format!("{} is the username of a person", id),
+            ));
+        }
+        state.store.save_user(&User {
+            username: id.clone(),
+            password_hash: auth::hash_password(&password)?,
+            role: Role::Device,
+        })?;
+    }
+
+    let device = Device {
+        last_seen: state.store.device(&id)?.and_then(|device| device.last_seen),
+        id,
+        groups: settings.groups,
+    };
+    state.store.save_device(&device)?;
+    info!("Device {} saved", device.id);
+    record(&state, &caller, "device.save", device.id.clone());
+
+    let users = state.store.users()?;
+    Ok(Json(DeviceBody::new(&state, device, &users)))
+}
+
+async fn remove_device(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    id: Result<Path<String>, PathRejection>,
+) -> Result<StatusCode, ApiError> {
+    let Path(id) = id?;
+    if !state.store.delete_device(&id)? {
+        return Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            "device not found",
+            "it may have been deleted already",
+        ));
+    }
+    if state
+        .store
+        .user(&id)?
+        .is_some_and(|user| user.role == Role::Device)
+    {
+        state.store.delete_user(&id)?;
+    }
+    info!("Device {} deleted", id);
+    record(&state, &caller, "device.delete", id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// audit
+
+/// Changes made through the form and the API, the newest first.
+async fn list_audit(
+    State(state): State<AppState>,
+    Query(limit): Query<Limit>,
+) -> Result<Json<Vec<AuditEntry>>, ApiError> {
+    Ok(Json(state.store.audit(limit.get())?))
+}
@@ -0,0 +1,270 @@
+//! Who is calling: a user from the store with basic auth, as admin, sender or device, or a
+//! device or script with a bearer token. The web form logs in with a session instead, see
+//! `login`.
+
+use crate::{
+    AppState,
+    storage::{DEFAULT_DEVICE, Role, Store, User},
+};
+use argon2::{
+    Argon2,
+    password_hash::{
+        PasswordHash, PasswordHasher, PasswordVerifier, SaltString,
+        rand_core::{OsRng, RngCore},
+    },
+};
+use axum::{
+    async_trait,
+    body::Body,
+    extract::{FromRequestParts, State},
+    http::{Request, StatusCode, header, request::Parts},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::{
+    Engine,
+    engine::general_purpose::{STANDARD as BASE64, URL_SAFE_NO_PAD},
+};
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use std::sync::LazyLock;
+use tracing::{error, info, warn};
+
+const MAX_ID_LENGTH: usize = 32;
+
+/// Checked for unknown usernames, so they take as long as wrong passwords.
+static DUMMY_HASH: LazyLock<String> =
+    LazyLock::new(|| hash_password("dummy password").unwrap_or_default());
+
+#[derive(Clone)]
+pub struct Caller {
+    pub username: String,
+    pub role: Role,
+}
+
+pub async fn middleware(
+    State(state): State<AppState>,
+    mut req: Request<Body>,
+    next: Next,
+) -> Response {
+    let auth_header = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok());
+
+    let caller = match auth_header {
+        Some(value) => match value.strip_prefix("Bearer ") {
+            Some(token) => check_token(&state, token),
+            None => check_basic(&state, value).await,
+        },
+        None => None,
+    };
+    if let Some(caller) = caller {
+        req.extensions_mut().insert(caller);
+        return next.run(req).await;
+    }
+
+    Response::builder()
+        .status(StatusCode::UNAUTHORIZED)
+        .header(header::WWW_AUTHENTICATE, "Basic realm=\"Message Server\"")
+        .body(Body::from("Unauthorized"))
+        .unwrap()
+}
+
+async fn check_basic(state: &AppState, auth_value: &str) -> Option<Caller> {
+    let encoded = auth_value.strip_prefix("Basic ")?;
+    let credentials = String::from_utf8(BASE64.decode(encoded).ok()?).ok()?;
+    let (username, password) = credentials.split_once(':')?;
+    check(state, username, password).await
+}
+
+/// The device or script with this token, remembering when it was used.
+fn check_token(state: &AppState, token: &str) -> Option<Caller> {
+    let token = match state.store.token(&token_hash(token)) {
+        Ok(Some(token)) => token,
+        Ok(None) => {
+            warn!("Failed login with an unknown token");
+            return None;
+        }
+        Err(e) => {
+            error!("Failed to read token: {:?}", e);
+            return None;
+        }
+    };
+    if let Err(e) = state.store.touch_token(token.id, Utc::now()) {
+        warn!("Failed to save when token {} was used: {:?}", token.name, e);
+    }
+
+    Some(Caller {
+        username: token.name,
+        role: token.role,
+    })
+}
+
+/// The user with this username and password, for basic auth and the login page.
+pub async fn check(state: &AppState, username: &str, password: &str) -> Option<Caller> {
+    // hashing takes a while, so keep it off the async workers
+    let store = state.store.clone();
+    let (username, password) = (username.to_string(), password.to_string());
+    let caller =
+        tokio::task::spawn_blocking(move || authenticate(&*store, &username, &password)).await;
+    match caller {
+        Ok(Ok(caller)) => Some(caller),
+        Ok(Err(username)) => {
+            warn!("Failed login as '{}'", username);
+            None
+        }
+        Err(e) => {
+            error!("Failed to check login: {:?}", e);
+            None
+        }
+    }
+}
+
+/// The caller with this username and password, or the username back when they don't match.
+fn authenticate(store: &dyn Store, username: &str, password: &str) -> Result<Caller, String> {
+    let user = match store.user(username) {
+        Ok(user) => user,
+        Err(e) => {
+            error!("Failed to read user {}: {:?}", username, e);
+            None
+        }
+    };
+    let hash = user
+        .as_ref()
+        .map_or(DUMMY_HASH.as_str(), |user| &user.password_hash);
+    let verified = verify_password(password, hash);
+
+    match user {
+        Some(user) if verified => Ok(Caller {
+            username: user.username,
+            role: user.role,
+        }),
+        _ => Err(username.to_string()),
+    }
+}
+
+/// Keeps devices to the routes for devices.
+pub async fn require_sender(req: Request<Body>, next: Next) -> Response {
+    require(Role::Sender, req, next).await
+}
+
+/// Keeps devices and senders out of the settings.
+pub async fn require_admin(req: Request<Body>, next: Next) -> Response {
+    require(Role::Admin, req, next).await
+}
+
+async fn require(role: Role, req: Request<Body>, next: Next) -> Response {
+    match req.extensions().get::<Caller>() {
+        Some(caller) if caller.role >= role => next.run(req).await,
+        _ => (StatusCode::FORBIDDEN, "Forbidden").into_response(),
+    }
+}
+
+/// Device a request is for. Devices are always themselves, people pick a known one with the
+/// `X-Device-Id` header.
+pub struct DeviceId(pub String);
+
+#[async_trait]
+impl FromRequestParts<AppState> for DeviceId {
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        match parts.extensions.get::<Caller>() {
+            Some(caller) if caller.role == Role::Device => Ok(DeviceId(caller.username.clone())),
+            Some(_) => {
+                let id = parts
+                    .headers
+                    .get("x-device-id")
+                    .map(|id| id.to_str().unwrap_or_default())
+                    .unwrap_or(DEFAULT_DEVICE);
+                if !is_valid_id(id) {
+                    return Err((StatusCode::BAD_REQUEST, "Invalid device id"));
+                }
+
+                // only devices add themselves when they show up, so a typo adds none
+                let known = id == DEFAULT_DEVICE
+                    || state
+                        .store
+                        .device(id)
+                        .map_err(|e| {
+                            error!("Failed to read device: {:?}", e);
+                            (StatusCode::INTERNAL_SERVER_ERROR, "Storage error")
+                        })?
+                        .is_some();
+                if !known {
+                    return Err((StatusCode::BAD_REQUEST, "Unknown device"));
+                }
+                Ok(DeviceId(id.to_string()))
+            }
+            None => Err((StatusCode::UNAUTHORIZED, "Unauthorized")),
+        }
+    }
+}
+
+/// Device ids and group names are short and made of letters, digits, `-` and `_`.
+pub fn is_valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_ID_LENGTH
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Creates the admin from `AUTH_USERNAME` and `AUTH_PASSWORD`, or updates the password
+/// when it changed there.
+pub fn ensure_admin(store: &dyn Store, username: &str, password: &str) -> anyhow::Result<()> {
+    let user = store.user(username)?;
+    if let Some(user) = &user
+        && user.role == Role::Admin
+        && verify_password(password, &user.password_hash)
+    {
+        return Ok(());
+    }
+
+    store.save_user(&User {
+        username: username.to_string(),
+        password_hash: hash_password(password)?,
+        role: Role::Admin,
+    })?;
+    if user.is_some() {
+        info!("Updated admin {} from AUTH_USERNAME", username);
+    } else {
+        info!("Added admin {} from AUTH_USERNAME", username);
+    }
+    Ok(())
+}
+
+/// A new random token and the hash to keep of it.
+pub fn new_token() -> (String, String) {
+    let mut bytes = [0; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let token = URL_SAFE_NO_PAD.encode(bytes);
+    let hash = token_hash(&token);
+    (token, hash)
+}
+
+/// Tokens are long and random, so a fast hash is enough to not keep them in plain text.
+fn token_hash(token: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(token.as_bytes()))
+}
+
+pub fn hash_password(password: &str) -> anyhow::Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|e| anyhow::anyhow!("failed to hash password: {}", e))?;
+    Ok(hash.to_string())
+}
+
+/// Argon2 compares the hashes in constant time.
+fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash).is_ok_and(|hash| {
+        Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok()
+    })
+}
@@ -0,0 +1,81 @@
+//! Subcommands to manage the users, run like `server user add alice sender`.
+
+use crate::{
+    auth,
+    storage::{self, Role, Store, User},
+};
+use anyhow::{Context, Result, bail};
+use std::io::{self, BufRead, Write};
+
+const USAGE: &str = "usage:
+    server user list
+    server user add <username> <admin|sender|device>
+    server user remove <username>";
+
+pub fn run(args: &[String]) -> Result<()> {
+    let store = storage::open()?;
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    match args.as_slice() {
+        ["user", "list"] => list_users(&*store),
+        ["user", "add", username, role] => add_user(&*store, username, role.parse()?),
+        ["user", "remove", username] => remove_user(&*store, username),
+        _ => bail!(USAGE),
+    }
+}
+
+fn list_users(store: &dyn Store) -> Result<()> {
+    for user in store.users()? {
+        println!("{} ({})", user.username, user.role.as_str());
+    }
+    Ok(())
+}
+
+/// Adds a user, or sets a new password and role for an existing one.
+fn add_user(store: &dyn Store, username: &str, role: Role) -> Result<()> {
+    if role == Role::Device && !auth::is_valid_id(username) {
+        bail!("device ids are letters, digits, - and _ only, up to 32");
+    }
+    if username.is_empty() || username.contains(':') {
+        bail!("usernames can't be empty or contain ':'");
+    }
+
+    let password = read_password(username)?;
+    let exists = store.user(username)?.is_some();
+    store.save_user(&User {
+        username: username.to_string(),
+        password_hash: auth::hash_password(&password)?,
+        role,
+    })?;
+    if exists {
+        println!("updated {}", username);
+    } else {
+        println!("added {}", username);
+    }
+    Ok(())
+}
+
+fn remove_user(store: &dyn Store, username: &str) -> Result<()> {
+    if !store.delete_user(username)? {
+        bail!("no user {}", username);
+    }
+    println!("removed {}", username);
+    Ok(())
+}
+
+/// Reads the password from stdin, so it can be piped in and never ends up in the shell
+/// history.
+fn read_password(username: &str) -> Result<String> {
+    eprint!("password for {}: ", username);
+    io::stderr().flush()?;
+
+    let mut password = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut password)
+        .context("failed to read password")?;
+    let password = password.trim_end_matches(['\r', '\n']);
+    if password.is_empty() {
+        bail!("the password can't be empty");
+    }
+    Ok(password.to_string())
+}
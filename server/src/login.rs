@@ -0,0 +1,385 @@
+//! Login for the web form: a signed session cookie instead of basic auth prompts, and a CSRF
+//! token for every form that changes something, the login form included.
+
+use crate::{AppState, auth, auth::Caller, html_escape, storage::Role};
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use axum::{
+    Extension, Form,
+    body::{Body, to_bytes},
+    extract::State,
+    http::{HeaderMap, Method, Request, StatusCode, header},
+    middleware::Next,
+    response::{AppendHeaders, Html, IntoResponse, Redirect, Response},
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD as BASE64};
+use chrono::{Duration, Utc};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use std::{env, sync::LazyLock};
+use tracing::{error, info, warn};
+
+const COOKIE_NAME: &str = "session";
+/// Cookie with the nonce the CSRF token of the login form is signed for, there is no session
+/// yet to sign it for.
+const LOGIN_COOKIE_NAME: &str = "login";
+/// How long the login form can be left open.
+const LOGIN_MINUTES: i64 = 60;
+/// Long enough that a shared tablet stays logged in.
+const SESSION_DAYS: i64 = 30;
+const MAX_FORM_SIZE: usize = 64 * 1024;
+
+/// Whether the form is served over HTTPS, from `SESSION_SECURE`, so browsers never send the
+/// cookies unencrypted.
+static SECURE: LazyLock<bool> =
+    LazyLock::new(|| env::var("SESSION_SECURE").is_ok_and(|secure| secure == "true"));
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Key to sign the session cookies with, from `SESSION_SECRET` or random, which logs everyone
+/// out on restart.
+pub fn key() -> Vec<u8> {
+    match env::var("SESSION_SECRET") {
+        Ok(secret) if !secret.is_empty() => secret.into_bytes(),
+        _ => {
+            warn!("SESSION_SECRET is not set, logins end when the server restarts");
+            let mut key = vec![0; 32];
+            OsRng.fill_bytes(&mut key);
+            key
+        }
+    }
+}
+
+/// Token the forms of a session send along, so other sites can't post them.
+#[derive(Clone)]
+pub struct CsrfToken(pub String);
+
+fn mac(key: &[u8], parts: &[&[u8]]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC takes keys of any length");
+    for part in parts {
+        mac.update(part);
+        mac.update(&[0]);
+    }
+    mac
+}
+
+/// Signs the username and expiry with the password hash, so changing the password ends the
+/// sessions.
+fn session_mac(key: &[u8], username: &str, expires: i64, password_hash: &str) -> HmacSha256 {
+    mac(
+        key,
+        &[
+            username.as_bytes(),
+            expires.to_string().as_bytes(),
+            password_hash.as_bytes(),
+        ],
+    )
+}
+
+fn csrf_mac(key: &[u8], signature: &[u8]) -> HmacSha256 {
+    mac(key, &[b"csrf", signature])
+}
+
+fn login_csrf_mac(key: &[u8], nonce: &[u8]) -> HmacSha256 {
+    mac(key, &[b"login", nonce])
+}
+
+fn cookie(name: &str, value: &str, max_age: i64) -> String {
+    let secure = if *SECURE { "; Secure" } else { "" };
+    format!("{name}={value}; Path=/; HttpOnly; SameSite=Lax; Max-Age={max_age}{secure}")
+}
+
+fn session_cookie(value: &str, max_age: i64) -> String {
+    cookie(COOKIE_NAME, value, max_age)
+}
+
+fn cookie_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|cookie| cookie.to_str().ok())
+        .flat_map(|cookie| cookie.split(';'))
+        .find_map(|pair| pair.trim().strip_prefix(name)?.strip_prefix('='))
+}
+
+/// The logged in sender or admin with the signature of their session cookie.
+fn session(state: &AppState, headers: &HeaderMap) -> Option<(Caller, Vec<u8>)> {
+    let mut parts = cookie_value(headers, COOKIE_NAME)?.split('.');
+    let username = String::from_utf8(BASE64.decode(parts.next()?).ok()?).ok()?;
+    let expires: i64 = parts.next()?.parse().ok()?;
+    let signature = BASE64.decode(parts.next()?).ok()?;
+    if expires < Utc::now().timestamp() {
+        return None;
+    }
+
+    let user = match state.store.user(&username) {
+        Ok(user) => user?,
+        Err(e) => {
+            error!("Failed to read user {}: {:?}", username, e);
+            return None;
+        }
+    };
+    session_mac(&state.session_key, &username, expires, &user.password_hash)
+        .verify_slice(&signature)
+        .ok()?;
+    // devices never get a session, but a user could have been changed to one since
+    if user.role < Role::Sender {
+        return None;
+    }
+
+    let caller = Caller {
+        username: user.username,
+        role: user.role,
+    };
+    Some((caller, signature))
+}
+
+#[derive(Deserialize)]
+struct CsrfForm {
+    #[serde(default)]
+    csrf: String,
+}
+
+/// Lets only logged in senders and admins through to the form, and checks the CSRF token of
+/// everything they post.
+pub async fn middleware(State(state): State<AppState>, req: Request<Body>, next: Next) -> Response {
+    let Some((caller, signature)) = session(&state, req.headers()) else {
+        return Redirect::to("/login").into_response();
+    };
+    let csrf = csrf_mac(&state.session_key, &signature);
+
+    let mut req = if req.method() == Method::POST {
+        let (parts, body) = req.into_parts();
+        let Ok(bytes) = to_bytes(body, MAX_FORM_SIZE).await else {
+            return (StatusCode::PAYLOAD_TOO_LARGE, "Form too large").into_response();
+        };
+        let sent = serde_urlencoded::from_bytes::<CsrfForm>(&bytes)
+            .ok()
+            .and_then(|form| BASE64.decode(form.csrf).ok())
+            .unwrap_or_default();
+        if csrf.clone().verify_slice(&sent).is_err() {
+            warn!(
+                "Rejected a form of {} without a valid CSRF token",
+                caller.username
+            );
+            return (StatusCode::FORBIDDEN, "Invalid CSRF token").into_response();
+        }
+        Request::from_parts(parts, Body::from(bytes))
+    } else {
+        req
+    };
+
+    req.extensions_mut().insert(caller);
+    req.extensions_mut()
+        .insert(CsrfToken(BASE64.encode(csrf.finalize().into_bytes())));
+    next.run(req).await
+}
+
+pub async fn show_login(State(state): State<AppState>) -> Response {
+    login_page(&state, StatusCode::OK, "")
+}
+
+#[derive(Deserialize)]
+pub struct LoginForm {
+    username: String,
+    password: String,
+    #[serde(default)]
+    csrf: String,
+}
+
+pub async fn login(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(form): Form<LoginForm>,
+) -> Response {
+    // only the login page sets the cookie, so another site can't log a tablet into its account
+
+    let nonce = cookie_value(&headers, LOGIN_COOKIE_NAME)
+        .and_then(|nonce| BASE64.decode(nonce).ok())
+        .unwrap_or_default();
+    let sent = BASE64.decode(&form.csrf).unwrap_or_default();
+    if nonce.is_empty()
+        || login_csrf_mac(&state.session_key, &nonce)
+            .verify_slice(&sent)
+            .is_err()
+    {
+        warn!("Rejected a login without a valid CSRF token");
+        return login_page(
+            &state,
+            StatusCode::FORBIDDEN,
+            "the login form expired, please try again",
+        );
+    }
+
+    let Some(caller) = auth::check(&state, &form.username, &form.password).await else {
+        return login_page(
+            &state,
+            StatusCode::UNAUTHORIZED,
+            "wrong username or password",
+        );
+    };
+    if caller.role < Role::Sender {
+        return login_page(&state, StatusCode::FORBIDDEN, "devices can't use the form");
+    }
+    let user = match state.store.user(&caller.username) {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            return login_page(
+                &state,
+                StatusCode::UNAUTHORIZED,
+                "wrong username or password",
+            );
+        }
+        Err(e) => {
+            error!("Failed to read user {}: {:?}", caller.username, e);
+            return login_page(&state, StatusCode::INTERNAL_SERVER_ERROR, "storage error");
+        }
+    };
+
+    let expires = (Utc::now() + Duration::days(SESSION_DAYS)).timestamp();
+    let signature = session_mac(
+        &state.session_key,
+        &user.username,
+        expires,
+        &user.password_hash,
+    )
+    .finalize()
+    .into_bytes();
+    let value = format!(
+        "{}.{}.{}",
+        BASE64.encode(&user.username),
+        expires,
+        BASE64.encode(signature)
+    );
+
+    info!("{} logged in", user.username);
+    (
+        AppendHeaders([
+            (
+                header::SET_COOKIE,
+                session_cookie(&value, SESSION_DAYS * 24 * 60 * 60),
+            ),
+            (header::SET_COOKIE, cookie(LOGIN_COOKIE_NAME, "", 0)),
+        ]),
+        Redirect::to("/form"),
+    )
+        .into_response()
+}
+
+pub async fn logout(Extension(caller): Extension<Caller>) -> Response {
+    info!("{} logged out", caller.username);
+    (
+        [(header::SET_COOKIE, session_cookie("", 0))],
+        Redirect::to("/login"),
+    )
+        .into_response()
+}
+
+/// The login form with a new CSRF token, signed for a nonce in a cookie.
+fn login_page(state: &AppState, status: StatusCode, error: &str) -> Response {
+    let mut nonce = [0; 16];
+    OsRng.fill_bytes(&mut nonce);
+    let csrf = BASE64.encode(
+        login_csrf_mac(&state.session_key, &nonce)
+            .finalize()
+            .into_bytes(),
+    );
+
+    let error = if error.is_empty() {
+        String::new()
+    } else {
+        format!(r#"<div class="error">{}</div>"#, html_escape(error))
+    };
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+    <title>Log In</title>
+    <style>
+        * {{
+            margin: 0;
+            padding: 0;
+            box-sizing: border-box;
+        }}
+        body {{
+            font-family: monospace;
+            background: #000;
+            color: #fff;
+            padding: 20px;
+            max-width: 600px;
+            margin: 0 auto;
+        }}
+        h1 {{
+            font-size: 18px;
+            font-weight: normal;
+            margin-bottom: 20px;
+            border-bottom: 1px solid #333;
+            padding-bottom: 10px;
+        }}
+        form {{
+            display: flex;
+            flex-direction: column;
+            gap: 20px;
+        }}
+        label {{
+            display: block;
+            margin-bottom: 5px;
+            color: #999;
+        }}
+        input {{
+            width: 100%;
+            padding: 10px;
+            background: #000;
+            color: #fff;
+            border: 1px solid #333;
+            font-family: monospace;
+            font-size: 14px;
+        }}
+        input:focus {{
+            outline: none;
+            border-color: #666;
+        }}
+        button {{
+            padding: 10px 20px;
+            background: #fff;
+            color: #000;
+            border: none;
+            font-family: monospace;
+            cursor: pointer;
+            width: fit-content;
+        }}
+        button:hover {{
+            background: #ccc;
+        }}
+        .error {{
+            border: 1px solid #333;
+            padding: 15px;
+            margin-bottom: 20px;
+        }}
+    </style>
+</head>
+<body>
+    <h1>log in</h1>
+    {}
+    <form method="POST" action="/login">
+        <input type="hidden" name="csrf" value="{}">
+        <div>
+            <label for="username">username</label>
+            <input id="username" name="username" autocomplete="username" required autofocus>
+        </div>
+        <div>
+            <label for="password">password</label>
+            <input type="password" id="password" name="password" autocomplete="current-password" required>
+        </div>
+
+        <button type="submit">log in</button>
+    </form>
+</body>
+</html>"#,
+        error, csrf
+    );
+    let cookie = cookie(LOGIN_COOKIE_NAME, &BASE64.encode(nonce), LOGIN_MINUTES * 60);
+    (status, [(header::SET_COOKIE, cookie)], Html(html)).into_response()
+}
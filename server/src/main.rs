@@ -1,33 +1,70 @@
+use auth::{Caller, DeviceId};
 use axum::{
-    Form, Router,
+    Extension, Form, Json, Router,
     body::Body,
     extract::State,
-    http::{Request, StatusCode, header},
-    middleware::{self, Next},
-    response::{Html, IntoResponse, Response},
-    routing::get,
+    http::{StatusCode, header},
+    middleware,
+    response::{
+        Html, IntoResponse, Redirect, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+    routing::{get, post},
 };
-use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use chrono::{DateTime, Duration, Utc, Weekday};
+use login::CsrfToken;
 use serde::Deserialize;
-use std::{env, fs, path::PathBuf, sync::Arc};
-use tokio::sync::RwLock;
-use tracing::{debug, info, warn};
+use std::{
+    collections::HashMap,
+    env,
+    sync::{Arc, Mutex},
+};
+use storage::{Alarm, Device, Message, Role, Session, Store};
+use tokio::sync::watch;
+use tokio_stream::{Stream, StreamExt, wrappers::WatchStream};
+use tracing::{debug, error, info, warn};
 use tracing_subscriber::prelude::*;
 
-const MESSAGE_FILE: &str = "message.txt";
+mod api;
+mod auth;
+mod cli;
+mod login;
+mod storage;
+mod tokens;
+
 const MAX_MESSAGE_LENGTH: usize = 144;
 
 #[derive(Clone)]
 struct AppState {
-    username: String,
-    password: String,
-    message_path: PathBuf,
-    message: Arc<RwLock<String>>,
+    store: Arc<dyn Store>,
+    /// Version of the latest message sent, to push changes to the devices.
+    message_updates: Arc<watch::Sender<i64>>,
+    /// Version of the last message sent, the next one always gets a higher one.
+    last_version: Arc<Mutex<i64>>,
+    /// Open event streams by device.
+    subscribers: Arc<Mutex<HashMap<String, usize>>>,
+    /// Signs the session cookies of the web form.
+    session_key: Arc<[u8]>,
+    cube: Option<Cube>,
+    client: reqwest::Client,
+}
+
+/// Control API of the cube, to mirror its screen.
+#[derive(Clone)]
+struct Cube {
+    url: String,
+    token: String,
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     dotenv::dotenv().ok();
+
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if !args.is_empty() {
+        return cli::run(&args);
+    }
+
     tracing_subscriber::registry()
         .with(tracing_subscriber::EnvFilter::new(
             env::var("RUST_LOG").unwrap_or_else(|_| "info,server=debug".into()),
@@ -35,32 +72,70 @@ async fn main() -> anyhow::Result<()> {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
-    let username = env::var("AUTH_USERNAME").expect("AUTH_USERNAME not set");
-    let password = env::var("AUTH_PASSWORD").expect("AUTH_PASSWORD not set");
+    let store = storage::open()?;
+    if let (Ok(username), Ok(password)) = (env::var("AUTH_USERNAME"), env::var("AUTH_PASSWORD")) {
+        auth::ensure_admin(&*store, &username, &password)?;
+    }
+    if store.users()?.is_empty() {
+        warn!("No users yet, add one with `server user add <username> admin`");
+    }
 
-    let message_path = PathBuf::from(MESSAGE_FILE);
-
-    // Load existing message or create default
-    let initial_message = if message_path.exists() {
-        fs::read_to_string(&message_path).unwrap_or_else(|_| "No message yet".to_string())
-    } else {
-        "No message yet".to_string()
-    };
+    // the cube's screen is only shown when its control API is reachable
+    let cube = env::var("CUBE_URL")
+        .ok()
+        .filter(|url| !url.is_empty())
+        .map(|url| Cube {
+            url: url.trim_end_matches('/').to_string(),
+            token: env::var("CUBE_TOKEN").unwrap_or_default(),
+        });
 
     let state = AppState {
-        username,
-        password,
-        message_path,
-        message: Arc::new(RwLock::new(initial_message)),
+        store,
+        message_updates: Arc::new(watch::Sender::new(0)),
+        last_version: Arc::default(),
+        subscribers: Arc::default(),
+        session_key: login::key().into(),
+        cube,
+        client: reqwest::Client::new(),
     };
 
-    let app = Router::new()
-        .route("/", get(get_message))
+    // the web form logs in with a session, devices and scripts with basic auth or a token
+    let admin = Router::new()
+        .route(
+            "/tokens",
+            get(tokens::show_tokens).post(tokens::create_token),
+        )
+        .route("/tokens/revoke", post(tokens::revoke_token))
+        .route_layer(middleware::from_fn(auth::require_admin));
+    let web = Router::new()
         .route("/form", get(show_form).post(update_message))
-        .layer(middleware::from_fn_with_state(
+        .route("/alarms", post(add_alarm))
+        .route("/alarms/delete", post(delete_alarm))
+        .route("/screen", get(screen))
+        .route("/logout", post(login::logout))
+        .merge(admin)
+        .route_layer(middleware::from_fn_with_state(
             state.clone(),
-            auth_middleware,
-        ))
+            login::middleware,
+        ));
+    let people = Router::new()
+        .nest("/api/v1", api::router())
+        .route_layer(middleware::from_fn(auth::require_sender));
+    let devices = Router::new()
+        .route("/", get(get_message))
+        .route("/events", get(message_events))
+        .route("/seen", post(mark_seen))
+        .route("/alarms", get(get_alarms))
+        .route("/pomodoro", post(add_sessions))
+        .merge(people)
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::middleware,
+        ));
+    let app = Router::new()
+        .route("/login", get(login::show_login).post(login::login))
+        .merge(devices)
+        .merge(web)
         .with_state(state);
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await?;
@@ -71,49 +146,154 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
-async fn auth_middleware(
-    State(state): State<AppState>,
-    req: Request<Body>,
-    next: Next,
-) -> Response {
-    let auth_header = req
-        .headers()
-        .get(header::AUTHORIZATION)
-        .and_then(|h| h.to_str().ok());
-
-    if let Some(auth_value) = auth_header
-        && let Some(encoded) = auth_value.strip_prefix("Basic ")
-        && let Ok(decoded) = BASE64.decode(encoded)
-        && let Ok(credentials) = String::from_utf8(decoded)
-    {
-        let parts: Vec<&str> = credentials.splitn(2, ':').collect();
-        if parts.len() == 2 {
-            if parts[0] == state.username && parts[1] == state.password {
-                return next.run(req).await;
-            } else {
-                warn!(
-                    "Failed authentication attempt - username: '{}', password: '{}'",
-                    parts[0], parts[1]
-                );
+async fn get_message(State(state): State<AppState>, DeviceId(device): DeviceId) -> Response {
+    touch_device(&state, &device);
+    match state.store.message(&device) {
+        Ok(Some(message)) => {
+            debug!("Message read by {}: {}", device, message.text);
+            (
+                [("x-message-version", message.version.to_string())],
+                message.text,
+            )
+                .into_response()
+        }
+        Ok(None) => "No message yet".into_response(),
+        Err(e) => {
+            error!("Failed to read message: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Remembers when a device was last seen, for the devices list.
+fn touch_device(state: &AppState, device: &str) {
+    if let Err(e) = state.store.touch_device(device, Utc::now()) {
+        warn!("Failed to save when {} was seen: {:?}", device, e);
+    }
+}
+
+/// Counts a device as subscribed while its event stream is open.
+struct Subscriber {
+    subscribers: Arc<Mutex<HashMap<String, usize>>>,
+    device: String,
+}
+
+impl Subscriber {
+    fn new(state: &AppState, device: &str) -> Self {
+        let subscribers = state.subscribers.clone();
+        *lock(&subscribers).entry(device.to_string()).or_default() += 1;
+        Subscriber {
+            subscribers,
+            device: device.to_string(),
+        }
+    }
+}
+
+impl Drop for Subscriber {
+    fn drop(&mut self) {
+        let mut subscribers = lock(&self.subscribers);
+        if let Some(count) = subscribers.get_mut(&self.device) {
+            *count -= 1;
+            if *count == 0 {
+                subscribers.remove(&self.device);
             }
         }
     }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Pushes the device's message as server-sent events, the current one right away and then
+/// every change.
+async fn message_events(
+    State(state): State<AppState>,
+    DeviceId(device): DeviceId,
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    debug!("{} subscribed to message updates", device);
+    touch_device(&state, &device);
+    let subscriber = Subscriber::new(&state, &device);
+
+    // every new message wakes all streams, each only sends when its device got a new one
+    let mut sent = None;
+    let updates = WatchStream::new(state.message_updates.subscribe()).filter_map(move |_| {
+        let _subscriber = &subscriber;
+        let message = match state.store.message(&device) {
+            Ok(message) => message,
+            Err(e) => return Some(Err(axum::Error::new(e))),
+        };
+        let version = message.as_ref().map(|message| message.version);
+        if sent == Some(version) {
+            return None;
+        }
+        sent = Some(version);
 
-    Response::builder()
-        .status(StatusCode::UNAUTHORIZED)
-        .header(header::WWW_AUTHENTICATE, "Basic realm=\"Message Server\"")
-        .body(Body::from("Unauthorized"))
-        .unwrap()
+        let (text, version) = match message {
+            Some(message) => (message.text, Some(message.version.to_string())),
+            None => ("No message yet".to_string(), None),
+        };
+        Some(
+            Event::default()
+                .event("message")
+                .json_data(serde_json::json!({ "text": text, "version": version })),
+        )
+    });
+    Sse::new(updates).keep_alive(KeepAlive::default())
 }
 
-async fn get_message(State(state): State<AppState>) -> String {
-    let message = state.message.read().await;
-    debug!("Message read: {}", message);
-    message.clone()
+async fn mark_seen(
+    State(state): State<AppState>,
+    DeviceId(device): DeviceId,
+    body: String,
+) -> StatusCode {
+    let Ok(version) = body.trim().parse() else {
+        return StatusCode::BAD_REQUEST;
+    };
+    match state.store.mark_seen(&device, version, Utc::now()) {
+        Ok(true) => {
+            info!("Message seen on {}", device);
+            StatusCode::NO_CONTENT
+        }
+        Ok(false) => {
+            debug!(
+                "Ignoring read receipt for outdated message version {}",
+                body
+            );
+            StatusCode::CONFLICT
+        }
+        Err(e) => {
+            error!("Failed to save read receipt: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
 }
 
-async fn show_form(State(state): State<AppState>) -> Html<String> {
-    let current_message = state.message.read().await;
+async fn show_form(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Extension(CsrfToken(csrf)): Extension<CsrfToken>,
+) -> Response {
+    let stored = inboxes(&state).and_then(|inboxes| {
+        let devices = state.store.devices()?;
+        Ok((
+            inboxes,
+            devices,
+            state.store.alarms()?,
+            state.store.sessions()?,
+        ))
+    });
+    let (inboxes, devices, alarms, sessions) = match stored {
+        Ok(stored) => stored,
+        Err(e) => {
+            error!("Failed to load the form: {:?}", e);
+            return error_page(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "storage error",
+                &e.to_string(),
+            );
+        }
+    };
     let html = format!(
         r#"<!DOCTYPE html>
 <html>
@@ -200,18 +380,66 @@ async fn show_form(State(state): State<AppState>) -> Html<String> {
             color: #999;
             margin-bottom: 10px;
         }}
+        .seen {{
+            color: #666;
+            font-size: 12px;
+            margin-top: 10px;
+        }}
+        .alarm {{
+            display: flex;
+            justify-content: space-between;
+            align-items: center;
+            margin-bottom: 10px;
+        }}
+        .choices {{
+            display: flex;
+            flex-wrap: wrap;
+            gap: 10px;
+        }}
+        .choices label {{
+            display: flex;
+            gap: 5px;
+            margin: 0;
+        }}
+        .choices input {{
+            width: auto;
+        }}
+        .account {{
+            display: flex;
+            justify-content: space-between;
+            align-items: center;
+            color: #999;
+            margin-bottom: 20px;
+        }}
+        .account a {{
+            color: #999;
+        }}
+        .screen {{
+            display: block;
+            width: 100%;
+            image-rendering: pixelated;
+        }}
     </style>
 </head>
 <body>
     <div class="container">
+        <div class="account">
+            <span>logged in as {}{}</span>
+            <form method="POST" action="/logout">
+                <input type="hidden" name="csrf" value="{}">
+                <button type="submit">log out</button>
+            </form>
+        </div>
+
         <h1>update message</h1>
 
         <div class="current">
             <div class="current-label">current:</div>
-            <div>{}</div>
+            {}
         </div>
 
         <form method="POST" action="/form">
+            <input type="hidden" name="csrf" value="{}">
             <div>
                 <label for="message">message (max 144 characters)</label>
                 <textarea
@@ -225,9 +453,48 @@ async fn show_form(State(state): State<AppState>) -> Html<String> {
                     <span id="charCount">0</span> / {} characters
                 </div>
             </div>
+            <div>
+                <label>to (none picked sends to every device)</label>
+                <div class="choices">{}</div>
+            </div>
 
             <button type="submit">update</button>
         </form>
+
+        <h1 style="margin-top: 40px">alarms</h1>
+
+        <div class="current">{}</div>
+
+        <form method="POST" action="/alarms">
+            <input type="hidden" name="csrf" value="{}">
+            <div>
+                <label for="time">time</label>
+                <input type="time" id="time" name="time" required>
+            </div>
+            <div>
+                <label>repeat on</label>
+                <div class="choices">
+                    <label><input type="checkbox" name="mon">mon</label>
+                    <label><input type="checkbox" name="tue">tue</label>
+                    <label><input type="checkbox" name="wed">wed</label>
+                    <label><input type="checkbox" name="thu">thu</label>
+                    <label><input type="checkbox" name="fri">fri</label>
+                    <label><input type="checkbox" name="sat">sat</label>
+                    <label><input type="checkbox" name="sun">sun</label>
+                </div>
+            </div>
+            <div>
+                <label for="date">or once on</label>
+                <input type="date" id="date" name="date">
+            </div>
+
+            <button type="submit">add alarm</button>
+        </form>
+
+        <h1 style="margin-top: 40px">focus this week</h1>
+
+        <div class="current">{}</div>
+        {}
     </div>
 
     <script>
@@ -239,7 +506,8 @@ async fn show_form(State(state): State<AppState>) -> Html<String> {
 
         function filterInput(event) {{
             const textarea = event.target;
-            const allowedPattern = /[a-zA-Z0-9 !,.\-#+?;:_'*\/"()]/g;
+            // ISO 8859-15 (Latin-9), the char set the cube's display fonts can draw
+            const allowedPattern = /[ -~\u00A0-\u00A3\u00A5\u00A7\u00A9-\u00B3\u00B5-\u00B7\u00B9-\u00BB\u00BF-\u00FF\u20AC\u0160\u0161\u017D\u017E\u0152\u0153\u0178]/g;
             const filtered = textarea.value.match(allowedPattern);
             const newValue = filtered ? filtered.join('') : '';
 
@@ -254,56 +522,209 @@ async fn show_form(State(state): State<AppState>) -> Html<String> {
         }}
 
         document.getElementById('message').addEventListener('input', filterInput);
+
+        // show the read receipt in the browser's local time
+        document.querySelectorAll('time[datetime]').forEach((el) => {{
+            el.textContent = new Date(el.getAttribute('datetime'))
+                .toLocaleTimeString([], {{ hour: '2-digit', minute: '2-digit' }});
+        }});
     </script>
 </body>
 </html>"#,
-        html_escape(&current_message),
+        html_escape(&caller.username),
+        if caller.role == Role::Admin {
+            r#" · <a href="/tokens">api tokens</a>"#
+        } else {
+            ""
+        },
+        csrf,
+        inbox_list(&inboxes),
+        csrf,
+        MAX_MESSAGE_LENGTH,
         MAX_MESSAGE_LENGTH,
-        MAX_MESSAGE_LENGTH
+        recipient_choices(&devices),
+        alarm_list(&alarms, &csrf),
+        csrf,
+        focus_summary(&sessions),
+        if state.cube.is_some() {
+            r#"
+        <h1 style="margin-top: 40px">screen</h1>
+
+        <div class="current"><img class="screen" src="/screen" alt="live screen of the cube"></div>"#
+        } else {
+            ""
+        },
     );
-    Html(html)
+    Html(html).into_response()
 }
 
-#[derive(Deserialize)]
-struct MessageForm {
-    message: String,
+/// Relays the live screen of the cube, so the browser doesn't need the cube's token.
+async fn screen(State(state): State<AppState>) -> Response {
+    let Some(cube) = &state.cube else {
+        return error_page(StatusCode::NOT_FOUND, "no cube", "CUBE_URL is not set");
+    };
+
+    let response = state
+        .client
+        .get(format!("{}/screen.mjpeg", cube.url))
+        .bearer_auth(&cube.token)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+    match response {
+        Ok(response) => {
+            let content_type = response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .cloned()
+                .unwrap_or(header::HeaderValue::from_static(
+                    "multipart/x-mixed-replace",
+                ));
+            Response::builder()
+                .header(header::CONTENT_TYPE, content_type)
+                .header(header::CACHE_CONTROL, "no-cache")
+                .body(Body::from_stream(response.bytes_stream()))
+                .unwrap()
+        }
+        Err(err) => {
+            warn!("Failed to stream the cube's screen: {err}");
+            error_page(
+                StatusCode::BAD_GATEWAY,
+                "cube unreachable",
+                &err.to_string(),
+            )
+        }
+    }
 }
 
-async fn update_message(State(state): State<AppState>, Form(form): Form<MessageForm>) -> Response {
-    // Validate message length
-    if form.message.len() > MAX_MESSAGE_LENGTH {
-        return (
-            StatusCode::BAD_REQUEST,
-            Html(format!(
-                "<style>body{{font-family:monospace;background:#000;color:#fff;padding:20px;}}a{{color:#fff;}}</style><h1>message too long</h1><p>maximum {} characters allowed</p><a href=\"/form\">try again</a>",
-                MAX_MESSAGE_LENGTH
-            )),
-        )
-            .into_response();
+/// Current message of every device, or of the single cube before any device showed up.
+fn inboxes(state: &AppState) -> anyhow::Result<Vec<(String, Option<Message>)>> {
+    let mut ids = state
+        .store
+        .devices()?
+        .into_iter()
+        .map(|device| device.id)
+        .collect::<Vec<_>>();
+    if ids.is_empty() {
+        ids.push(storage::DEFAULT_DEVICE.to_string());
+    }
+
+    ids.into_iter()
+        .map(|id| {
+            let message = state.store.message(&id)?;
+            Ok((id, message))
+        })
+        .collect()
+}
+
+fn inbox_list(inboxes: &[(String, Option<Message>)]) -> String {
+    inboxes
+        .iter()
+        .map(|(device, message)| {
+            let text = message
+                .as_ref()
+                .map_or("No message yet".to_string(), |message| {
+                    html_escape(&message.text)
+                });
+            format!(
+                r#"<div>{}: {}</div>
+            <div class="seen">{}</div>"#,
+                device,
+                text,
+                seen_label(message.as_ref().and_then(|message| message.seen_at))
+            )
+        })
+        .collect()
+}
+
+fn seen_label(seen_at: Option<DateTime<Utc>>) -> String {
+    match seen_at {
+        Some(seen_at) => format!(
+            "seen at <time datetime=\"{}\">{} UTC</time>",
+            seen_at.to_rfc3339(),
+            seen_at.format("%H:%M")
+        ),
+        None => "not seen yet".to_string(),
     }
+}
 
-    let trimmed_message = form.message.trim().to_string();
+/// Checkboxes for the groups and devices to send a message to.
+fn recipient_choices(devices: &[Device]) -> String {
+    let mut groups = devices
+        .iter()
+        .flat_map(|device| device.groups.iter())
+        .collect::<Vec<_>>();
+    groups.sort();
+    groups.dedup();
 
-    // Save to disk
-    if let Err(e) = fs::write(&state.message_path, &trimmed_message) {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Html(format!(
-                "<style>body{{font-family:monospace;background:#000;color:#fff;padding:20px;}}a{{color:#fff;}}</style><h1>error saving message</h1><p>{}</p><a href=\"/form\">try again</a>",
-                e
-            )),
+    let groups = groups.into_iter().map(|group| {
+        format!(r#"<label><input type="checkbox" name="group:{group}">group {group}</label>"#)
+    });
+    let devices = devices.iter().map(|device| {
+        format!(
+            r#"<label><input type="checkbox" name="device:{0}">{0}</label>"#,
+            device.id
         )
-            .into_response();
+    });
+    groups.chain(devices).collect()
+}
+
+fn alarm_list(alarms: &[(i64, Alarm)], csrf: &str) -> String {
+    if alarms.is_empty() {
+        return "<div class=\"seen\">no alarms set</div>".to_string();
     }
 
-    // Update in-memory state
-    let mut message = state.message.write().await;
-    let old_message = message.clone();
-    *message = trimmed_message.clone();
-    info!(
-        "Message changed from '{}' to '{}'",
-        old_message, trimmed_message
-    );
+    alarms
+        .iter()
+        .map(|(id, alarm)| {
+            let repeat = match alarm.date {
+                Some(date) if alarm.days.is_empty() => date.format("%Y-%m-%d").to_string(),
+                _ => alarm
+                    .days
+                    .iter()
+                    .map(|day| day.to_string().to_lowercase())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            };
+            format!(
+                r#"<div class="alarm">
+                <span>{:02}:{:02} {}</span>
+                <form method="POST" action="/alarms/delete">
+                    <input type="hidden" name="csrf" value="{}">
+                    <input type="hidden" name="id" value="{}">
+                    <button type="submit">delete</button>
+                </form>
+            </div>"#,
+                alarm.hour, alarm.minute, repeat, csrf, id
+            )
+        })
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct MessageForm {
+    message: String,
+    /// Checked recipients, like `device:kitchen` or `group:upstairs`.
+    #[serde(flatten)]
+    recipients: HashMap<String, String>,
+}
+
+async fn update_message(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Form(form): Form<MessageForm>,
+) -> Response {
+    let mut to = api::Recipients::default();
+    for recipient in form.recipients.keys() {
+        if let Some(device) = recipient.strip_prefix("device:") {
+            to.devices.push(device.to_string());
+        } else if let Some(group) = recipient.strip_prefix("group:") {
+            to.groups.push(group.to_string());
+        }
+    }
+    if let Err(err) = api::send_message(&state, &caller, &form.message, to).await {
+        return err.into_page();
+    }
 
     Html(
         r#"<!DOCTYPE html>
@@ -349,6 +770,144 @@ async fn update_message(State(state): State<AppState>, Form(form): Form<MessageF
     .into_response()
 }
 
+async fn get_alarms(State(state): State<AppState>) -> Response {
+    match state.store.alarms() {
+        Ok(alarms) => Json(
+            alarms
+                .into_iter()
+                .map(|(_, alarm)| alarm)
+                .collect::<Vec<_>>(),
+        )
+        .into_response(),
+        Err(e) => {
+            error!("Failed to read alarms: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct AlarmForm {
+    time: String,
+    #[serde(default)]
+    date: String,
+    mon: Option<String>,
+    tue: Option<String>,
+    wed: Option<String>,
+    thu: Option<String>,
+    fri: Option<String>,
+    sat: Option<String>,
+    sun: Option<String>,
+}
+
+async fn add_alarm(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Form(form): Form<AlarmForm>,
+) -> Response {
+    let days = [
+        (&form.mon, Weekday::Mon),
+        (&form.tue, Weekday::Tue),
+        (&form.wed, Weekday::Wed),
+        (&form.thu, Weekday::Thu),
+        (&form.fri, Weekday::Fri),
+        (&form.sat, Weekday::Sat),
+        (&form.sun, Weekday::Sun),
+    ]
+    .into_iter()
+    .filter_map(|(checked, day)| checked.as_ref().map(|_| day))
+    .collect();
+
+    let alarm = api::NewAlarm {
+        time: form.time,
+        days,
+        date: Some(form.date),
+    };
+    if let Err(err) = api::add_alarm(&state, &caller, alarm).await {
+        return err.into_page();
+    }
+
+    Redirect::to("/form").into_response()
+}
+
+#[derive(Deserialize)]
+struct DeleteAlarmForm {
+    id: i64,
+}
+
+async fn delete_alarm(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Form(form): Form<DeleteAlarmForm>,
+) -> Response {
+    if let Err(err) = api::delete_alarm(&state, &caller, form.id).await {
+        return err.into_page();
+    }
+
+    Redirect::to("/form").into_response()
+}
+
+/// Focus blocks of the last seven days, one line per day.
+fn focus_summary(sessions: &[Session]) -> String {
+    let today = Utc::now().date_naive();
+    let days = (0..7).rev().map(|n| today - Duration::days(n));
+
+    let mut total = 0;
+    let mut rows = days
+        .map(|day| {
+            let of_day = sessions
+                .iter()
+                .filter(|session| session.started_at.date_naive() == day);
+            let (blocks, minutes) = of_day.fold((0, 0), |(blocks, minutes), session| {
+                (blocks + session.completed as i64, minutes + session.minutes)
+            });
+            total += minutes;
+            format!(
+                "<div>{} {}: {} blocks, {} min</div>",
+                day.format("%a").to_string().to_lowercase(),
+                day.format("%m-%d"),
+                blocks,
+                minutes
+            )
+        })
+        .collect::<String>();
+    rows.push_str(&format!(
+        "<div class=\"seen\">{} h {} min in total</div>",
+        total / 60,
+        total % 60
+    ));
+    rows
+}
+
+async fn add_sessions(
+    State(state): State<AppState>,
+    Json(reported): Json<Vec<Session>>,
+) -> StatusCode {
+    // Reports are retried, the store skips sessions that already arrived
+    match state.store.add_sessions(&reported) {
+        Ok(new) => {
+            info!("Received {} focus sessions", new);
+            StatusCode::NO_CONTENT
+        }
+        Err(e) => {
+            warn!("Failed to save focus sessions: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+fn error_page(status: StatusCode, title: &str, detail: &str) -> Response {
+    (
+        status,
+        Html(format!(
+            "<style>body{{font-family:monospace;background:#000;color:#fff;padding:20px;}}a{{color:#fff;}}</style><h1>{}</h1><p>{}</p><a href=\"/form\">try again</a>",
+            title,
+            html_escape(detail)
+        )),
+    )
+        .into_response()
+}
+
 fn html_escape(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
@@ -0,0 +1,389 @@
+use super::{
+    Alarm, ApiToken, AuditEntry, DEFAULT_DEVICE, Device, Message, Role, Session, Store, User,
+};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard},
+};
+
+/// Plain text message of the single cube, from before there were several devices.
+const MESSAGE_FILE: &str = "message.txt";
+const MESSAGES_FILE: &str = "messages.json";
+const ALARMS_FILE: &str = "alarms.json";
+const SESSIONS_FILE: &str = "pomodoro.json";
+const DEVICES_FILE: &str = "devices.json";
+const USERS_FILE: &str = "users.json";
+const TOKENS_FILE: &str = "tokens.json";
+const AUDIT_FILE: &str = "audit.jsonl";
+
+/// How often when a device was seen is written, devices poll far more often.
+const TOUCH_INTERVAL: Duration = Duration::minutes(1);
+
+/// Keeps the state in JSON files, with only the current message of each device.
+pub struct FileStore {
+    dir: PathBuf,
+    state: Mutex<Files>,
+}
+
+struct Files {
+    /// Current message by device.
+    messages: HashMap<String, Message>,
+    alarms: Vec<Alarm>,
+    sessions: Vec<Session>,
+    devices: Vec<Device>,
+    users: Vec<User>,
+    tokens: Vec<ApiToken>,
+}
+
+/// Devices kept their own password before there were users.
+#[derive(Deserialize)]
+struct DevicePassword {
+    id: String,
+    password_hash: Option<String>,
+}
+
+impl FileStore {
+    pub fn open(dir: &Path) -> Result<Self> {
+        fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+
+        let mut messages: HashMap<String, Message> = load_json(&dir.join(MESSAGES_FILE));
+        if messages.is_empty()
+            && let Ok(text) = fs::read_to_string(dir.join(MESSAGE_FILE))
+        {
+            let message = Message {
+                text,
+                version: Utc::now().timestamp_millis(),
+                seen_at: None,
+            };
+            messages.insert(DEFAULT_DEVICE.to_string(), message);
+        }
+
+        let users_path = dir.join(USERS_FILE);
+        let users = if users_path.exists() {
+            load_json(&users_path)
+        } else {
+            let devices: Vec<DevicePassword> = load_json(&dir.join(DEVICES_FILE));
+            let users = devices
+                .into_iter()
+                .filter_map(|device| {
+                    Some(User {
+                        username: device.id,
+                        password_hash: device.password_hash?,
+                        role: Role::Device,
+                    })
+                })
+                .collect::<Vec<_>>();
+            if !users.is_empty() {
+                save_json(&users_path, &users)?;
+            }
+            users
+        };
+
+        Ok(FileStore {
+            dir: dir.to_path_buf(),
+            state: Mutex::new(Files {
+                messages,
+                alarms: load_json(&dir.join(ALARMS_FILE)),
+                sessions: load_json(&dir.join(SESSIONS_FILE)),
+                devices: load_json(&dir.join(DEVICES_FILE)),
+                users,
+                tokens: load_json(&dir.join(TOKENS_FILE)),
+            }),
+        })
+    }
+
+    fn state(&self) -> MutexGuard<'_, Files> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Store for FileStore {
+    fn message(&self, device: &str) -> Result<Option<Message>> {
+        Ok(self.state().messages.get(device).cloned())
+    }
+
+    fn messages(&self, device: &str, limit: usize) -> Result<Vec<Message>> {
+        Ok(self.message(device)?.into_iter().take(limit).collect())
+    }
+
+    fn set_message(&self, device: &str, message: &Message) -> Result<()> {
+        let mut state = self.state();
+        let mut messages = state.messages.clone();
+        messages.insert(device.to_string(), message.clone());
+        save_json(&self.dir.join(MESSAGES_FILE), &messages)?;
+        state.messages = messages;
+        Ok(())
+    }
+
+    fn mark_seen(&self, device: &str, version: i64, at: DateTime<Utc>) -> Result<bool> {
+        let mut state = self.state();
+        let mut messages = state.messages.clone();
+        match messages.get_mut(device) {
+            Some(message) if message.version == version => {
+                if message.seen_at.is_none() {
+                    message.seen_at = Some(at);
+                    save_json(&self.dir.join(MESSAGES_FILE), &messages)?;
+                    state.messages = messages;
+                }
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn alarms(&self) -> Result<Vec<(i64, Alarm)>> {
+        Ok((0..)
+            .zip(self.state().alarms.iter().cloned())
+            .collect::<Vec<_>>())
+    }
+
+    fn add_alarm(&self, alarm: &Alarm) -> Result<i64> {
+        let mut state = self.state();
+        let mut alarms = state.alarms.clone();
+        alarms.push(alarm.clone());
+        save_json(&self.dir.join(ALARMS_FILE), &alarms)?;
+        state.alarms = alarms;
+        Ok(state.alarms.len() as i64 - 1)
+    }
+
+    fn delete_alarm(&self, id: i64) -> Result<bool> {
+        let mut state = self.state();
+        let Some(index) = usize::try_from(id).ok().filter(|&i| i < state.alarms.len()) else {
+            return Ok(false);
+        };
+        let mut alarms = state.alarms.clone();
+        alarms.remove(index);
+        save_json(&self.dir.join(ALARMS_FILE), &alarms)?;
+        state.alarms = alarms;
+        Ok(true)
+    }
+
+    fn sessions(&self) -> Result<Vec<Session>> {
+        Ok(self.state().sessions.clone())
+    }
+
+    fn add_sessions(&self, sessions: &[Session]) -> Result<usize> {
+        let mut state = self.state();
+        let new = sessions
+            .iter()
+            .filter(|session| {
+                state
+                    .sessions
+                    .iter()
+                    .all(|s| s.started_at != session.started_at)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        if new.is_empty() {
+            return Ok(0);
+        }
+
+        let mut all = state.sessions.clone();
+        all.extend(new.iter().cloned());
+        save_json(&self.dir.join(SESSIONS_FILE), &all)?;
+        state.sessions = all;
+        Ok(new.len())
+    }
+
+    fn devices(&self) -> Result<Vec<Device>> {
+        Ok(self.state().devices.clone())
+    }
+
+    fn device(&self, id: &str) -> Result<Option<Device>> {
+        Ok(self
+            .state()
+            .devices
+            .iter()
+            .find(|device| device.id == id)
+            .cloned())
+    }
+
+    fn save_device(&self, device: &Device) -> Result<()> {
+        let mut state = self.state();
+        let mut devices = state.devices.clone();
+        match devices.iter_mut().find(|d| d.id == device.id) {
+            Some(saved) => saved.groups = device.groups.clone(),
+            None => devices.push(device.clone()),
+        }
+        save_json(&self.dir.join(DEVICES_FILE), &devices)?;
+        state.devices = devices;
+        Ok(())
+    }
+
+    fn delete_device(&self, id: &str) -> Result<bool> {
+        let mut state = self.state();
+        let mut devices = state.devices.clone();
+        devices.retain(|device| device.id != id);
+        if devices.len() == state.devices.len() {
+            return Ok(false);
+        }
+
+        let mut messages = state.messages.clone();
+        messages.remove(id);
+        save_json(&self.dir.join(DEVICES_FILE), &devices)?;
+        save_json(&self.dir.join(MESSAGES_FILE), &messages)?;
+        state.devices = devices;
+        state.messages = messages;
+        Ok(true)
+    }
+
+    fn touch_device(&self, id: &str, at: DateTime<Utc>) -> Result<()> {
+        let mut state = self.state();
+        let mut devices = state.devices.clone();
+        match devices.iter_mut().find(|device| device.id == id) {
+            Some(device)
+                if device
+                    .last_seen
+                    .is_some_and(|seen| at - seen < TOUCH_INTERVAL) =>
+            {
+                return Ok(());
+            }
+            Some(device) => device.last_seen = Some(at),
+            None => devices.push(Device {
+                id: id.to_string(),
+                groups: Vec::new(),
+                last_seen: Some(at),
+            }),
+        }
+        save_json(&self.dir.join(DEVICES_FILE), &devices)?;
+        state.devices = devices;
+        Ok(())
+    }
+
+    fn users(&self) -> Result<Vec<User>> {
+        Ok(self.state().users.clone())
+    }
+
+    fn user(&self, username: &str) -> Result<Option<User>> {
+        Ok(self
+            .state()
+            .users
+            .iter()
+            .find(|user| user.username == username)
+            .cloned())
+    }
+
+    fn save_user(&self, user: &User) -> Result<()> {
+        let mut state = self.state();
+        let mut users = state.users.clone();
+        match users.iter_mut().find(|u| u.username == user.username) {
+            Some(saved) => *saved = user.clone(),
+            None => users.push(user.clone()),
+        }
+        save_json(&self.dir.join(USERS_FILE), &users)?;
+        state.users = users;
+        Ok(())
+    }
+
+    fn delete_user(&self, username: &str) -> Result<bool> {
+        let mut state = self.state();
+        let mut users = state.users.clone();
+        users.retain(|user| user.username != username);
+        if users.len() == state.users.len() {
+            return Ok(false);
+        }
+
+        save_json(&self.dir.join(USERS_FILE), &users)?;
+        state.users = users;
+        Ok(true)
+    }
+
+    fn tokens(&self) -> Result<Vec<ApiToken>> {
+        Ok(self.state().tokens.clone())
+    }
+
+    fn token(&self, token_hash: &str) -> Result<Option<ApiToken>> {
+        Ok(self
+            .state()
+            .tokens
+            .iter()
+            .find(|token| token.token_hash == token_hash)
+            .cloned())
+    }
+
+    fn add_token(&self, token: &ApiToken) -> Result<i64> {
+        let mut state = self.state();
+        let mut tokens = state.tokens.clone();
+        let id = tokens.iter().map(|token| token.id).max().unwrap_or(0) + 1;
+        tokens.push(ApiToken {
+            id,
+            ..token.clone()
+        });
+        save_json(&self.dir.join(TOKENS_FILE), &tokens)?;
+        state.tokens = tokens;
+        Ok(id)
+    }
+
+    fn delete_token(&self, id: i64) -> Result<bool> {
+        let mut state = self.state();
+        let mut tokens = state.tokens.clone();
+        tokens.retain(|token| token.id != id);
+        if tokens.len() == state.tokens.len() {
+            return Ok(false);
+        }
+
+        save_json(&self.dir.join(TOKENS_FILE), &tokens)?;
+        state.tokens = tokens;
+        Ok(true)
+    }
+
+    fn touch_token(&self, id: i64, at: DateTime<Utc>) -> Result<()> {
+        let mut state = self.state();
+        let mut tokens = state.tokens.clone();
+        if let Some(token) = tokens.iter_mut().find(|token| token.id == id) {
+            token.last_used = Some(at);
+            save_json(&self.dir.join(TOKENS_FILE), &tokens)?;
+            state.tokens = tokens;
+        }
+        Ok(())
+    }
+
+    fn record(&self, entry: &AuditEntry) -> Result<()> {
+        let _state = self.state();
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join(AUDIT_FILE))?;
+        writeln!(file, "{}", serde_json::to_string(entry)?)?;
+        Ok(())
+    }
+
+    fn audit(&self, limit: usize) -> Result<Vec<AuditEntry>> {
+        let _state = self.state();
+        let Ok(lines) = fs::read_to_string(self.dir.join(AUDIT_FILE)) else {
+            return Ok(Vec::new());
+        };
+        Ok(lines
+            .lines()
+            .rev()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .take(limit)
+            .collect())
+    }
+}
+
+fn load_json<T: DeserializeOwned + Default>(path: &Path) -> T {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn save_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<()> {
+    write_atomic(path, serde_json::to_string_pretty(value)?.as_bytes())
+}
+
+/// Writes next to `path` first and moves the file over, so a crash never leaves half a file.
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    fs::write(&tmp, contents).with_context(|| format!("failed to write {}", path.display()))?;
+    fs::rename(&tmp, path).with_context(|| format!("failed to replace {}", path.display()))?;
+    Ok(())
+}
@@ -0,0 +1,425 @@
+//! Where the server keeps its state: plain files in `DATA_DIR` or a SQLite database.
+
+mod file;
+mod sqlite;
+
+pub use file::FileStore;
+pub use sqlite::SqliteStore;
+
+use anyhow::{Result, bail};
+use chrono::{DateTime, NaiveDate, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use std::{env, path::PathBuf, str::FromStr, sync::Arc};
+use tracing::info;
+
+/// Device the messages from before there were several devices belong to.
+pub const DEFAULT_DEVICE: &str = "cube";
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Message {
+    pub text: String,
+    pub version: i64,
+    pub seen_at: Option<DateTime<Utc>>,
+}
+
+/// Alarm in the format the cube reads from `/alarms`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Alarm {
+    pub hour: u32,
+    pub minute: u32,
+    pub days: Vec<Weekday>,
+    pub date: Option<NaiveDate>,
+    pub enabled: bool,
+}
+
+/// Pomodoro focus block reported by the cube.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub started_at: DateTime<Utc>,
+    pub minutes: i64,
+    pub completed: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Device {
+    pub id: String,
+    /// Groups to send messages to several devices at once, like `kitchen`.
+    #[serde(default)]
+    pub groups: Vec<String>,
+    /// Last time the device asked for the message.
+    pub last_seen: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Reads its own messages, the username is the device id.
+    Device,
+    /// Sends messages and sets alarms.
+    Sender,
+    /// Also manages devices and reads the audit history.
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::Device => "device",
+            Role::Sender => "sender",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = anyhow::Error;
+
+    fn from_str(role: &str) -> Result<Self> {
+        match role {
+            "device" => Ok(Role::Device),
+            "sender" => Ok(Role::Sender),
+            "admin" => Ok(Role::Admin),
+            _ => bail!("unknown role '{}', expected device, sender or admin", role),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct User {
+    pub username: String,
+    /// Argon2 hash in PHC format.
+    pub password_hash: String,
+    pub role: Role,
+}
+
+/// Bearer token of a device or a script, revoked by deleting it.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub id: i64,
+    /// The device id for device tokens, so they read that device's inbox.
+    pub name: String,
+    /// `Device` to read messages and alarms, `Sender` to also send messages and set alarms.
+    pub role: Role,
+    /// SHA-256 of the token, the token itself is only shown once.
+    pub token_hash: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used: Option<DateTime<Utc>>,
+}
+
+/// A change someone made, kept as history.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub at: DateTime<Utc>,
+    /// Username of who made the change.
+    pub actor: String,
+    pub action: String,
+    pub detail: String,
+}
+
+pub trait Store: Send + Sync {
+    /// The current message of a device, if it ever got one.
+    fn message(&self, device: &str) -> Result<Option<Message>>;
+    /// Past messages of a device, the newest first.
+    fn messages(&self, device: &str, limit: usize) -> Result<Vec<Message>>;
+    fn set_message(&self, device: &str, message: &Message) -> Result<()>;
+    /// Marks the current message of a device as seen, false when `version` is not the
+    /// current one.
+    fn mark_seen(&self, device: &str, version: i64, at: DateTime<Utc>) -> Result<bool>;
+
+    /// Alarms with their ids.
+    fn alarms(&self) -> Result<Vec<(i64, Alarm)>>;
+    fn add_alarm(&self, alarm: &Alarm) -> Result<i64>;
+    /// Deletes an alarm, false when there is none with `id`.
+    fn delete_alarm(&self, id: i64) -> Result<bool>;
+
+    fn sessions(&self) -> Result<Vec<Session>>;
+    /// Adds the sessions that are not stored yet and returns how many that were.
+    fn add_sessions(&self, sessions: &[Session]) -> Result<usize>;
+
+    fn devices(&self) -> Result<Vec<Device>>;
+    fn device(&self, id: &str) -> Result<Option<Device>>;
+    /// Adds or updates a device, keeping when it was last seen.
+    fn save_device(&self, device: &Device) -> Result<()>;
+    /// Deletes a device with its messages, false when there is none with `id`.
+    fn delete_device(&self, id: &str) -> Result<bool>;
+    /// Remembers when a device was seen, adding it when it's new.
+    fn touch_device(&self, id: &str, at: DateTime<Utc>) -> Result<()>;
+
+    fn users(&self) -> Result<Vec<User>>;
+    fn user(&self, username: &str) -> Result<Option<User>>;
+    /// Adds a user or replaces the one with the same username.
+    fn save_user(&self, user: &User) -> Result<()>;
+    /// Deletes a user, false when there is none with `username`.
+    fn delete_user(&self, username: &str) -> Result<bool>;
+
+    fn tokens(&self) -> Result<Vec<ApiToken>>;
+    /// The token with this hash.
+    fn token(&self, token_hash: &str) -> Result<Option<ApiToken>>;
+    /// Adds a token and returns its id, ignoring the one it has.
+    fn add_token(&self, token: &ApiToken) -> Result<i64>;
+    /// Revokes a token, false when there is none with `id`.
+    fn delete_token(&self, id: i64) -> Result<bool>;
+    /// Remembers when a token was last used.
+    fn touch_token(&self, id: i64, at: DateTime<Utc>) -> Result<()>;
+
+    fn record(&self, entry: &AuditEntry) -> Result<()>;
+    /// Audit history, the newest first.
+    fn audit(&self, limit: usize) -> Result<Vec<AuditEntry>>;
+}
+
+/// Opens the store picked with `STORAGE`, `file` (default) or `sqlite`. A new SQLite
+/// database takes over what the files in `DATA_DIR` hold.
+pub fn open() -> Result<Arc<dyn Store>> {
+    let dir = PathBuf::from(env::var("DATA_DIR").unwrap_or(".".into()));
+    let files = FileStore::open(&dir)?;
+
+    match env::var("STORAGE").unwrap_or("file".into()).as_str() {
+        "sqlite" => {
+            let path = env::var("SQLITE_PATH")
+                .unwrap_or_else(|_| dir.join("server.db").to_string_lossy().into_owned());
+            info!("Using SQLite storage at {}", path);
+            let database = SqliteStore::open(&path)?;
+            if is_empty(&database)? && !is_empty(&files)? {
+                info!("Importing data files into the database");
+                import(&files, &database)?;
+            }
+            Ok(Arc::new(database))
+        }
+        _ => {
+            info!("Using file storage in {}", dir.display());
+            Ok(Arc::new(files))
+        }
+    }
+}
+
+fn is_empty(store: &dyn Store) -> Result<bool> {
+    Ok(store.users()?.is_empty()
+        && store.tokens()?.is_empty()
+        && store.devices()?.is_empty()
+        && store.message(DEFAULT_DEVICE)?.is_none()
+        && store.alarms()?.is_empty()
+        && store.sessions()?.is_empty())
+}
+
+fn import(from: &dyn Store, to: &dyn Store) -> Result<()> {
+    for user in from.users()? {
+        to.save_user(&user)?;
+    }
+    for token in from.tokens()? {
+        to.add_token(&token)?;
+    }
+    let devices = from.devices()?;
+    for device in &devices {
+        to.save_device(device)?;
+        if let Some(last_seen) = device.last_seen {
+            to.touch_device(&device.id, last_seen)?;
+        }
+    }
+    let ids = devices.iter().map(|device| device.id.as_str());
+    for id in ids.chain([DEFAULT_DEVICE]) {
+        if let Some(message) = from.message(id)? {
+            to.set_message(id, &message)?;
+        }
+    }
+    for (_, alarm) in from.alarms()? {
+        to.add_alarm(&alarm)?;
+    }
+    to.add_sessions(&from.sessions()?)?;
+    for entry in from.audit(usize::MAX)?.iter().rev() {
+        to.record(entry)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::{fs, path::Path};
+
+    /// A fresh directory for a file store, removed again when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = env::temp_dir().join(format!("server-test-{}-{}", std::process::id(), name));
+            let _ = fs::remove_dir_all(&dir);
+            TempDir(dir)
+        }
+
+        fn path(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Both backends, so each test checks they behave the same.
+    fn stores(dir: &TempDir) -> Vec<(&'static str, Box<dyn Store>)> {
+        vec![
+            ("file", Box::new(FileStore::open(dir.path()).unwrap())),
+            ("sqlite", Box::new(SqliteStore::open(":memory:").unwrap())),
+        ]
+    }
+
+    fn at(minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 1, 5, 8, minute, 0).unwrap()
+    }
+
+    fn message(text: &str, version: i64) -> Message {
+        Message {
+            text: text.to_string(),
+            version,
+            seen_at: None,
+        }
+    }
+
+    fn alarm(hour: u32) -> Alarm {
+        Alarm {
+            hour,
+            minute: 30,
+            days: vec![Weekday::Mon],
+            date: None,
+            enabled: true,
+        }
+    }
+
+    fn session(minute: u32) -> Session {
+        Session {
+            started_at: at(minute),
+            minutes: 25,
+            completed: true,
+        }
+    }
+
+    #[test]
+    fn mark_seen_only_accepts_the_current_version() {
+        let dir = TempDir::new("mark-seen");
+        for (name, store) in stores(&dir) {
+            store.set_message("kitchen", &message("old", 1)).unwrap();
+            store.set_message("kitchen", &message("new", 2)).unwrap();
+
+            assert!(!store.mark_seen("kitchen", 1, at(0)).unwrap(), "{name}");
+            assert!(!store.mark_seen("hallway", 2, at(0)).unwrap(), "{name}");
+            assert!(store.mark_seen("kitchen", 2, at(1)).unwrap(), "{name}");
+            // seen again keeps the first time
+            assert!(store.mark_seen("kitchen", 2, at(2)).unwrap(), "{name}");
+
+            let current = store.message("kitchen").unwrap().unwrap();
+            assert_eq!(current.text, "new", "{name}");
+            assert_eq!(current.seen_at, Some(at(1)), "{name}");
+        }
+    }
+
+    #[test]
+    fn add_sessions_skips_the_known_ones() {
+        let dir = TempDir::new("sessions");
+        for (name, store) in stores(&dir) {
+            assert_eq!(
+                store.add_sessions(&[session(0), session(30)]).unwrap(),
+                2,
+                "{name}"
+            );
+            assert_eq!(
+                store.add_sessions(&[session(30), session(55)]).unwrap(),
+                1,
+                "{name}"
+            );
+            assert_eq!(store.add_sessions(&[session(0)]).unwrap(), 0, "{name}");
+            assert_eq!(store.sessions().unwrap().len(), 3, "{name}");
+        }
+    }
+
+    #[test]
+    fn delete_alarm_tells_whether_there_was_one() {
+        let dir = TempDir::new("alarms");
+        for (name, store) in stores(&dir) {
+            store.add_alarm(&alarm(6)).unwrap();
+            let id = store.add_alarm(&alarm(7)).unwrap();
+
+            assert!(store.delete_alarm(id).unwrap(), "{name}");
+            assert!(!store.delete_alarm(id).unwrap(), "{name}");
+            assert!(!store.delete_alarm(-1).unwrap(), "{name}");
+
+            let alarms = store.alarms().unwrap();
+            assert_eq!(alarms.len(), 1, "{name}");
+            assert_eq!(alarms[0].1.hour, 6, "{name}");
+        }
+    }
+
+    #[test]
+    fn delete_device_removes_its_messages() {
+        let dir = TempDir::new("devices");
+        for (name, store) in stores(&dir) {
+            store.touch_device("kitchen", at(0)).unwrap();
+            store
+                .save_device(&Device {
+                    id: "kitchen".to_string(),
+                    groups: vec!["downstairs".to_string()],
+                    last_seen: None,
+                })
+                .unwrap();
+            store.set_message("kitchen", &message("hi", 1)).unwrap();
+
+            // saving keeps when the device was last seen
+            let device = store.device("kitchen").unwrap().unwrap();
+            assert_eq!(device.groups, ["downstairs"], "{name}");
+            assert_eq!(device.last_seen, Some(at(0)), "{name}");
+
+            assert!(store.delete_device("kitchen").unwrap(), "{name}");
+            assert!(!store.delete_device("kitchen").unwrap(), "{name}");
+            assert!(store.device("kitchen").unwrap().is_none(), "{name}");
+            assert!(store.message("kitchen").unwrap().is_none(), "{name}");
+        }
+    }
+
+    #[test]
+    fn import_copies_the_files_into_sqlite() {
+        let dir = TempDir::new("import");
+        let files = FileStore::open(dir.path()).unwrap();
+        files
+            .set_message(DEFAULT_DEVICE, &message("hello", 3))
+            .unwrap();
+        files.touch_device("kitchen", at(0)).unwrap();
+        files.set_message("kitchen", &message("dinner", 4)).unwrap();
+        files.add_alarm(&alarm(7)).unwrap();
+        files.add_sessions(&[session(0)]).unwrap();
+        files
+            .save_user(&User {
+                username: "alice".to_string(),
+                password_hash: "hash".to_string(),
+                role: Role::Sender,
+            })
+            .unwrap();
+        files
+            .record(&AuditEntry {
+                at: at(0),
+                actor: "alice".to_string(),
+                action: "alarm.add".to_string(),
+                detail: "07:30".to_string(),
+            })
+            .unwrap();
+
+        let database = SqliteStore::open(":memory:").unwrap();
+        assert!(is_empty(&database).unwrap());
+        assert!(!is_empty(&files).unwrap());
+        import(&files, &database).unwrap();
+
+        assert_eq!(
+            database.message(DEFAULT_DEVICE).unwrap().unwrap().text,
+            "hello"
+        );
+        assert_eq!(database.message("kitchen").unwrap().unwrap().version, 4);
+        let devices = database.devices().unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].last_seen, Some(at(0)));
+        assert_eq!(database.alarms().unwrap().len(), 1);
+        assert_eq!(database.sessions().unwrap().len(), 1);
+        assert!(database.user("alice").unwrap().unwrap().role == Role::Sender);
+        assert_eq!(database.audit(10).unwrap()[0].actor, "alice");
+    }
+}
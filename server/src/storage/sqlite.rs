@@ -0,0 +1,512 @@
+use super::{Alarm, ApiToken, AuditEntry, Device, Message, Role, Session, Store, User};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, OptionalExtension, Row, params};
+use std::sync::{Mutex, MutexGuard};
+use tracing::info;
+
+/// Schema changes, applied in order. `PRAGMA user_version` counts how many ran already, so
+/// only ever append to this list.
+const MIGRATIONS: &[&str] = &[
+    // 1: a single cube
+    r#"
+    CREATE TABLE messages (
+        version INTEGER PRIMARY KEY,
+        text TEXT NOT NULL,
+        seen_at TEXT
+    );
+    CREATE TABLE alarms (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        hour INTEGER NOT NULL,
+        minute INTEGER NOT NULL,
+        days TEXT NOT NULL,
+        date TEXT,
+        enabled INTEGER NOT NULL
+    );
+    CREATE TABLE sessions (
+        started_at TEXT PRIMARY KEY,
+        minutes INTEGER NOT NULL,
+        completed INTEGER NOT NULL
+    );
+    CREATE TABLE devices (
+        id TEXT PRIMARY KEY,
+        last_seen TEXT NOT NULL
+    );
+    CREATE TABLE audit (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        at TEXT NOT NULL,
+        actor TEXT NOT NULL,
+        action TEXT NOT NULL,
+        detail TEXT NOT NULL
+    );
+"#,
+    // 2: an inbox per device, devices with groups and their own login
+    r#"
+    CREATE TABLE inbox (
+        device TEXT NOT NULL,
+        version INTEGER NOT NULL,
+        text TEXT NOT NULL,
+        seen_at TEXT,
+        PRIMARY KEY (device, version)
+    );
+    -- the messages so far were all for the single cube
+    INSERT INTO inbox SELECT 'cube', version, text, seen_at FROM messages;
+    DROP TABLE messages;
+    ALTER TABLE inbox RENAME TO messages;
+
+    CREATE TABLE registered (
+        id TEXT PRIMARY KEY,
+        groups TEXT NOT NULL DEFAULT '[]',
+        password_hash TEXT,
+        last_seen TEXT
+    );
+    INSERT INTO registered (id, last_seen) SELECT id, last_seen FROM devices;
+    DROP TABLE devices;
+    ALTER TABLE registered RENAME TO devices;
+"#,
+    // 3: users with roles, devices log in as users
+    r#"
+    CREATE TABLE users (
+        username TEXT PRIMARY KEY,
+        password_hash TEXT NOT NULL,
+        role TEXT NOT NULL
+    );
+    INSERT INTO users (username, password_hash, role)
+        SELECT id, password_hash, 'device' FROM devices WHERE password_hash IS NOT NULL;
+    ALTER TABLE devices DROP COLUMN password_hash;
+"#,
+    // 4: bearer tokens for devices and scripts
+    r#"
+    CREATE TABLE tokens (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL,
+        role TEXT NOT NULL,
+        token_hash TEXT NOT NULL UNIQUE,
+        created_at TEXT NOT NULL,
+        last_used TEXT
+    );
+"#,
+];
+
+/// Keeps the state in a SQLite database, with the whole message history.
+pub struct SqliteStore {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    /// Opens the database at `path`, `:memory:` for one that only lives in memory.
+    pub fn open(path: &str) -> Result<Self> {
+        let mut connection =
+            Connection::open(path).with_context(|| format!("failed to open {}", path))?;
+        migrate(&mut connection)?;
+        Ok(SqliteStore {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    fn connection(&self) -> MutexGuard<'_, Connection> {
+        self.connection.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn migrate(connection: &mut Connection) -> Result<()> {
+    let applied: usize = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    for (version, migration) in MIGRATIONS.iter().enumerate().skip(applied) {
+        info!("Migrating database to version {}", version + 1);
+        let transaction = connection.transaction()?;
+        transaction
+            .execute_batch(migration)
+            .with_context(|| format!("failed to migrate to version {}", version + 1))?;
+        transaction.pragma_update(None, "user_version", version + 1)?;
+        transaction.commit()?;
+    }
+    Ok(())
+}
+
+fn message(row: &Row) -> rusqlite::Result<Message> {
+    Ok(Message {
+        version: row.get("version")?,
+        text: row.get("text")?,
+        seen_at: row.get("seen_at")?,
+    })
+}
+
+fn device(row: &Row) -> rusqlite::Result<Device> {
+    let groups: String = row.get("groups")?;
+    Ok(Device {
+        id: row.get("id")?,
+        groups: serde_json::from_str(&groups).unwrap_or_default(),
+        last_seen: row.get("last_seen")?,
+    })
+}
+
+fn user(row: &Row) -> rusqlite::Result<User> {
+    let role: String = row.get("role")?;
+    Ok(User {
+        username: row.get("username")?,
+        password_hash: row.get("password_hash")?,
+        // an unknown role gets the least rights
+        role: role.parse().unwrap_or(Role::Device),
+    })
+}
+
+fn token(row: &Row) -> rusqlite::Result<ApiToken> {
+    let role: String = row.get("role")?;
+    Ok(ApiToken {
+        id: row.get("id")?,
+        name: row.get("name")?,
+        role: role.parse().unwrap_or(Role::Device),
+        token_hash: row.get("token_hash")?,
+        created_at: row.get("created_at")?,
+        last_used: row.get("last_used")?,
+    })
+}
+
+fn alarm(row: &Row) -> rusqlite::Result<(i64, Alarm)> {
+    let days: String = row.get("days")?;
+    let alarm = Alarm {
+        hour: row.get("hour")?,
+        minute: row.get("minute")?,
+        days: serde_json::from_str(&days).unwrap_or_default(),
+        date: row.get("date")?,
+        enabled: row.get("enabled")?,
+    };
+    Ok((row.get("id")?, alarm))
+}
+
+impl Store for SqliteStore {
+    fn message(&self, device: &str) -> Result<Option<Message>> {
+        Ok(self
+            .connection()
+            .query_row(
+                "SELECT * FROM messages WHERE device = ?1 ORDER BY version DESC LIMIT 1",
+                [device],
+                message,
+            )
+            .optional()?)
+    }
+
+    fn messages(&self, device: &str, limit: usize) -> Result<Vec<Message>> {
+        let connection = self.connection();
+        let mut statement = connection
+            .prepare("SELECT * FROM messages WHERE device = ?1 ORDER BY version DESC LIMIT ?2")?;
+        let messages = statement
+            .query_map(params![device, limit as i64], message)?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(messages)
+    }
+
+    fn set_message(&self, device: &str, message: &Message) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR REPLACE INTO messages (device, version, text, seen_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![device, message.version, message.text, message.seen_at],
+        )?;
+        Ok(())
+    }
+
+    fn mark_seen(&self, device: &str, version: i64, at: DateTime<Utc>) -> Result<bool> {
+        let connection = self.connection();
+        let current: Option<i64> = connection.query_row(
+            "SELECT MAX(version) FROM messages WHERE device = ?1",
+            [device],
+            |row| row.get(0),
+        )?;
+        if current != Some(version) {
+            return Ok(false);
+        }
+
+        connection.execute(
+            "UPDATE messages SET seen_at = ?3
+             WHERE device = ?1 AND version = ?2 AND seen_at IS NULL",
+            params![device, version, at],
+        )?;
+        Ok(true)
+    }
+
+    fn alarms(&self) -> Result<Vec<(i64, Alarm)>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare("SELECT * FROM alarms ORDER BY id")?;
+        let alarms = statement
+            .query_map([], alarm)?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(alarms)
+    }
+
+    fn add_alarm(&self, alarm: &Alarm) -> Result<i64> {
+        let connection = self.connection();
+        connection.execute(
+            "INSERT INTO alarms (hour, minute, days, date, enabled) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                alarm.hour,
+                alarm.minute,
+                serde_json::to_string(&alarm.days)?,
+                alarm.date,
+                alarm.enabled
+            ],
+        )?;
+        Ok(connection.last_insert_rowid())
+    }
+
+    fn delete_alarm(&self, id: i64) -> Result<bool> {
+        let deleted = self
+            .connection()
+            .execute("DELETE FROM alarms WHERE id = ?1", [id])?;
+        Ok(deleted > 0)
+    }
+
+    fn sessions(&self) -> Result<Vec<Session>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare("SELECT * FROM sessions ORDER BY started_at")?;
+        let sessions = statement
+            .query_map([], |row| {
+                Ok(Session {
+                    started_at: row.get("started_at")?,
+                    minutes: row.get("minutes")?,
+                    completed: row.get("completed")?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(sessions)
+    }
+
+    fn add_sessions(&self, sessions: &[Session]) -> Result<usize> {
+        let mut connection = self.connection();
+        let transaction = connection.transaction()?;
+        let mut added = 0;
+        for session in sessions {
+            added += transaction.execute(
+                "INSERT OR IGNORE INTO sessions (started_at, minutes, completed) VALUES (?1, ?2, ?3)",
+                params![session.started_at, session.minutes, session.completed],
+            )?;
+        }
+        transaction.commit()?;
+        Ok(added)
+    }
+
+    fn devices(&self) -> Result<Vec<Device>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare("SELECT * FROM devices ORDER BY id")?;
+        let devices = statement
+            .query_map([], device)?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(devices)
+    }
+
+    fn device(&self, id: &str) -> Result<Option<Device>> {
+        Ok(self
+            .connection()
+            .query_row("SELECT * FROM devices WHERE id = ?1", [id], device)
+            .optional()?)
+    }
+
+    fn save_device(&self, device: &Device) -> Result<()> {
+        self.connection().execute(
+            "INSERT INTO devices (id, groups) VALUES (?1, ?2)
+             ON CONFLICT (id) DO UPDATE SET groups = excluded.groups",
+            params![device.id, serde_json::to_string(&device.groups)?],
+        )?;
+        Ok(())
+    }
+
+    fn delete_device(&self, id: &str) -> Result<bool> {
+        let mut connection = self.connection();
+        let transaction = connection.transaction()?;
+        transaction.execute("DELETE FROM messages WHERE device = ?1", [id])?;
+        let deleted = transaction.execute("DELETE FROM devices WHERE id = ?1", [id])?;
+        transaction.commit()?;
+        Ok(deleted > 0)
+    }
+
+    fn touch_device(&self, id: &str, at: DateTime<Utc>) -> Result<()> {
+        self.connection().execute(
+            "INSERT INTO devices (id, last_seen) VALUES (?1, ?2)
+             ON CONFLICT (id) DO UPDATE SET last_seen = excluded.last_seen",
+            params![id, at],
+        )?;
+        Ok(())
+    }
+
+    fn users(&self) -> Result<Vec<User>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare("SELECT * FROM users ORDER BY username")?;
+        let users = statement
+            .query_map([], user)?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(users)
+    }
+
+    fn user(&self, username: &str) -> Result<Option<User>> {
+        Ok(self
+            .connection()
+            .query_row("SELECT * FROM users WHERE username = ?1", [username], user)
+            .optional()?)
+    }
+
+    fn save_user(&self, user: &User) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR REPLACE INTO users (username, password_hash, role) VALUES (?1, ?2, ?3)",
+            params![user.username, user.password_hash, user.role.as_str()],
+        )?;
+        Ok(())
+    }
+
+    fn delete_user(&self, username: &str) -> Result<bool> {
+        let deleted = self
+            .connection()
+            .execute("DELETE FROM users WHERE username = ?1", [username])?;
+        Ok(deleted > 0)
+    }
+
+    fn tokens(&self) -> Result<Vec<ApiToken>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare("SELECT * FROM tokens ORDER BY id")?;
+        let tokens = statement
+            .query_map([], token)?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(tokens)
+    }
+
+    fn token(&self, token_hash: &str) -> Result<Option<ApiToken>> {
+        Ok(self
+            .connection()
+            .query_row(
+                "SELECT * FROM tokens WHERE token_hash = ?1",
+                [token_hash],
+                token,
+            )
+            .optional()?)
+    }
+
+    fn add_token(&self, token: &ApiToken) -> Result<i64> {
+        let connection = self.connection();
+        connection.execute(
+            "INSERT INTO tokens (name, role, token_hash, created_at, last_used)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                token.name,
+                token.role.as_str(),
+                token.token_hash,
+                token.created_at,
+                token.last_used
+            ],
+        )?;
+        Ok(connection.last_insert_rowid())
+    }
+
+    fn delete_token(&self, id: i64) -> Result<bool> {
+        let deleted = self
+            .connection()
+            .execute("DELETE FROM tokens WHERE id = ?1", [id])?;
+        Ok(deleted > 0)
+    }
+
+    fn touch_token(&self, id: i64, at: DateTime<Utc>) -> Result<()> {
+        self.connection().execute(
+            "UPDATE tokens SET last_used = ?2 WHERE id = ?1",
+            params![id, at],
+        )?;
+        Ok(())
+    }
+
+    fn record(&self, entry: &AuditEntry) -> Result<()> {
+        self.connection().execute(
+            "INSERT INTO audit (at, actor, action, detail) VALUES (?1, ?2, ?3, ?4)",
+            params![entry.at, entry.actor, entry.action, entry.detail],
+        )?;
+        Ok(())
+    }
+
+    fn audit(&self, limit: usize) -> Result<Vec<AuditEntry>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare("SELECT * FROM audit ORDER BY id DESC LIMIT ?1")?;
+        let entries = statement
+            .query_map([limit as i64], |row| {
+                Ok(AuditEntry {
+                    at: row.get("at")?,
+                    actor: row.get("actor")?,
+                    action: row.get("action")?,
+                    detail: row.get("detail")?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(connection: &Connection) -> usize {
+        connection
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    /// An in-memory database at `version`, with `data` inserted at that version.
+    fn database_at(version: usize, data: &str) -> Connection {
+        let connection = Connection::open_in_memory().unwrap();
+        for migration in &MIGRATIONS[..version] {
+            connection.execute_batch(migration).unwrap();
+        }
+        connection
+            .pragma_update(None, "user_version", version)
+            .unwrap();
+        connection.execute_batch(data).unwrap();
+        connection
+    }
+
+    fn store(mut connection: Connection) -> SqliteStore {
+        migrate(&mut connection).unwrap();
+        assert_eq!(version(&connection), MIGRATIONS.len());
+        SqliteStore {
+            connection: Mutex::new(connection),
+        }
+    }
+
+    #[test]
+    fn migrates_a_new_database() {
+        let store = SqliteStore::open(":memory:").unwrap();
+        assert_eq!(version(&store.connection()), MIGRATIONS.len());
+        assert!(store.devices().unwrap().is_empty());
+        assert!(store.users().unwrap().is_empty());
+        assert!(store.tokens().unwrap().is_empty());
+    }
+
+    #[test]
+    fn migrates_the_single_cube_from_version_1() {
+        let store = store(database_at(
+            1,
+            "INSERT INTO messages (version, text, seen_at) VALUES (1, 'old', NULL);
+             INSERT INTO messages (version, text, seen_at) VALUES (2, 'new', NULL);
+             INSERT INTO devices (id, last_seen) VALUES ('cube', '2026-01-05T08:00:00Z');
+             INSERT INTO alarms (hour, minute, days, date, enabled)
+                 VALUES (7, 30, '[\"Mon\"]', NULL, 1);",
+        ));
+
+        assert_eq!(store.message("cube").unwrap().unwrap().text, "new");
+        assert_eq!(store.messages("cube", 10).unwrap().len(), 2);
+        let devices = store.devices().unwrap();
+        assert_eq!(devices.len(), 1);
+        assert!(devices[0].groups.is_empty());
+        assert!(devices[0].last_seen.is_some());
+        assert_eq!(store.alarms().unwrap()[0].1.hour, 7);
+    }
+
+    #[test]
+    fn migrates_device_passwords_into_users() {
+        let store = store(database_at(
+            2,
+            "INSERT INTO devices (id, groups, password_hash) VALUES ('kitchen', '[]', 'hash');
+             INSERT INTO devices (id, groups) VALUES ('hallway', '[]');",
+        ));
+
+        let users = store.users().unwrap();
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].username, "kitchen");
+        assert_eq!(users[0].password_hash, "hash");
+        assert!(users[0].role == Role::Device);
+        assert_eq!(store.devices().unwrap().len(), 2);
+    }
+}
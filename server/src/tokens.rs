@@ -0,0 +1,292 @@
+//! Admin page to create and revoke the bearer tokens of devices and scripts.
+
+use crate::{
+    AppState, api,
+    auth::{self, Caller},
+    error_page, html_escape,
+    login::CsrfToken,
+    storage::{ApiToken, Role},
+};
+use axum::{
+    Extension, Form,
+    extract::State,
+    http::StatusCode,
+    response::{Html, IntoResponse, Redirect, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use tracing::{error, info};
+
+pub async fn show_tokens(
+    State(state): State<AppState>,
+    Extension(CsrfToken(csrf)): Extension<CsrfToken>,
+) -> Response {
+    tokens_page(&state, &csrf, None)
+}
+
+#[derive(Deserialize)]
+pub struct TokenForm {
+    name: String,
+    role: String,
+}
+
+pub async fn create_token(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Extension(CsrfToken(csrf)): Extension<CsrfToken>,
+    Form(form): Form<TokenForm>,
+) -> Response {
+    let role = match form.role.as_str() {
+        "device" => Role::Device,
+        "sender" => Role::Sender,
+        _ => {
+            return error_page(
+                StatusCode::BAD_REQUEST,
+                "invalid role",
+                "tokens are for devices or senders",
+            );
+        }
+    };
+    if !auth::is_valid_id(&form.name) {
+        return error_page(
+            StatusCode::BAD_REQUEST,
+            "invalid name",
+            "names are letters, digits, - and _ only, up to 32",
+        );
+    }
+
+    let (token, token_hash) = auth::new_token();
+    let new = ApiToken {
+        id: 0,
+        name: form.name,
+        role,
+        token_hash,
+        created_at: Utc::now(),
+        last_used: None,
+    };
+    if let Err(e) = state.store.add_token(&new) {
+        error!("Failed to save token: {:?}", e);
+        return error_page(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "storage error",
+            &e.to_string(),
+        );
+    }
+
+    info!("Created {} token {}", role.as_str(), new.name);
+    api::record(
+        &state,
+        &caller,
+        "token.create",
+        format!("{} ({})", new.name, role.as_str()),
+    );
+    tokens_page(&state, &csrf, Some((&new.name, &token)))
+}
+
+#[derive(Deserialize)]
+pub struct RevokeForm {
+    id: i64,
+}
+
+pub async fn revoke_token(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Form(form): Form<RevokeForm>,
+) -> Response {
+    match state.store.delete_token(form.id) {
+        Ok(true) => {
+            info!("Revoked token {}", form.id);
+            api::record(&state, &caller, "token.revoke", form.id.to_string());
+            Redirect::to("/tokens").into_response()
+        }
+        Ok(false) => error_page(StatusCode::NOT_FOUND, "no such token", &form.id.to_string()),
+        Err(e) => {
+            error!("Failed to revoke token: {:?}", e);
+            error_page(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "storage error",
+                &e.to_string(),
+            )
+        }
+    }
+}
+
+/// The tokens with a form for a new one, and the token just created, which is never shown
+/// again.
+fn tokens_page(state: &AppState, csrf: &str, created: Option<(&str, &str)>) -> Response {
+    let tokens = match state.store.tokens() {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            error!("Failed to read tokens: {:?}", e);
+            return error_page(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "storage error",
+                &e.to_string(),
+            );
+        }
+    };
+    let created = match created {
+        Some((name, token)) => format!(
+            r#"<div class="current">
+            <div class="current-label">new token for {}, copy it now, it is only shown once:</div>
+            <code>{}</code>
+        </div>"#,
+            html_escape(name),
+            token
+        ),
+        None => String::new(),
+    };
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+    <title>API Tokens</title>
+    <style>
+        * {{
+            margin: 0;
+            padding: 0;
+            box-sizing: border-box;
+        }}
+        body {{
+            font-family: monospace;
+            background: #000;
+            color: #fff;
+            padding: 20px;
+            max-width: 600px;
+            margin: 0 auto;
+        }}
+        h1 {{
+            font-size: 18px;
+            font-weight: normal;
+            margin-bottom: 20px;
+            border-bottom: 1px solid #333;
+            padding-bottom: 10px;
+        }}
+        a {{
+            color: #999;
+        }}
+        form {{
+            display: flex;
+            flex-direction: column;
+            gap: 20px;
+        }}
+        label {{
+            display: block;
+            margin-bottom: 5px;
+            color: #999;
+        }}
+        input, select {{
+            width: 100%;
+            padding: 10px;
+            background: #000;
+            color: #fff;
+            border: 1px solid #333;
+            font-family: monospace;
+            font-size: 14px;
+        }}
+        button {{
+            padding: 10px 20px;
+            background: #fff;
+            color: #000;
+            border: none;
+            font-family: monospace;
+            cursor: pointer;
+            width: fit-content;
+        }}
+        button:hover {{
+            background: #ccc;
+        }}
+        code {{
+            word-break: break-all;
+        }}
+        .current {{
+            padding: 15px;
+            border: 1px solid #333;
+            margin-bottom: 30px;
+        }}
+        .current-label {{
+            color: #999;
+            margin-bottom: 10px;
+        }}
+        .seen {{
+            color: #666;
+            font-size: 12px;
+        }}
+        .token {{
+            display: flex;
+            justify-content: space-between;
+            align-items: center;
+            gap: 10px;
+            margin-bottom: 10px;
+        }}
+    </style>
+</head>
+<body>
+    <h1>api tokens</h1>
+    <p style="margin-bottom: 20px"><a href="/form">back to the form</a></p>
+
+    {}
+
+    <div class="current">{}</div>
+
+    <form method="POST" action="/tokens">
+        <input type="hidden" name="csrf" value="{}">
+        <div>
+            <label for="name">name, the device id for devices</label>
+            <input id="name" name="name" maxlength="32" required>
+        </div>
+        <div>
+            <label for="role">can</label>
+            <select id="role" name="role">
+                <option value="device">read the messages of the device</option>
+                <option value="sender">send messages and set alarms</option>
+            </select>
+        </div>
+
+        <button type="submit">create token</button>
+    </form>
+</body>
+</html>"#,
+        created,
+        token_list(&tokens, csrf),
+        csrf
+    );
+    Html(html).into_response()
+}
+
+fn token_list(tokens: &[ApiToken], csrf: &str) -> String {
+    if tokens.is_empty() {
+        return "<div class=\"seen\">no tokens yet</div>".to_string();
+    }
+
+    tokens
+        .iter()
+        .map(|token| {
+            format!(
+                r#"<div class="token">
+            <div>
+                <div>{} ({})</div>
+                <div class="seen">created {}, last used {}</div>
+            </div>
+            <form method="POST" action="/tokens/revoke">
+                <input type="hidden" name="csrf" value="{}">
+                <input type="hidden" name="id" value="{}">
+                <button type="submit">revoke</button>
+            </form>
+        </div>"#,
+                html_escape(&token.name),
+                token.role.as_str(),
+                format_time(token.created_at),
+                token.last_used.map_or("never".to_string(), format_time),
+                csrf,
+                token.id
+            )
+        })
+        .collect()
+}
+
+fn format_time(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d %H:%M UTC").to_string()
+}